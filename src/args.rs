@@ -1,27 +1,121 @@
 use {
     clap::{
+        builder::PossibleValue,
         error::{
             Error,
             ErrorKind,
         },
+        Args,
         Parser,
+        Subcommand,
         ValueEnum,
     },
-    eyre::Result,
+    eyre::{
+        bail,
+        Result,
+    },
+    std::{
+        num::NonZeroUsize,
+        path::PathBuf,
+    },
     tracing::info,
+    utokyo_fsc_is4029l1_assignment_m1::{
+        Backend,
+        Format,
+        RendererKind,
+        SceneFormat,
+    },
 };
 
+/// Reads a scene from stdin or a file and writes its image to stdout or a file, unless a
+/// subcommand is given.
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Arguments {
-    pub input: Input,
-    pub output: Output,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Formats of the scene and the image in this order, either of which can be left out to be
+    /// told by the extension of `--input` or `--output`.
+    #[arg(value_name = "FORMAT", num_args = 0..=2)]
+    formats: Vec<FormatArgument>,
+    /// Format of the scene, which is the positional one that names a scene format.
+    #[arg(skip)]
+    pub input_format: Option<SceneFormat>,
+    /// Format of the image, which is the positional one that names an image format.
+    #[arg(skip)]
+    pub output_format: Option<Format>,
+    /// Read the scene from this path instead of stdin. `-` means stdin.
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+    /// Write the image to this path instead of stdout. `-` means stdout.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+    /// Render again whenever the scene file changes until interrupted, which needs both `--input`
+    /// and `--output`.
+    #[arg(long)]
+    pub watch: bool,
+    #[command(flatten)]
+    pub render: RenderArguments,
+    /// Write which renderer and adapter drew the image to this path as JSON.
+    #[arg(long)]
+    pub summary: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Render many scene files into image files next to them or in a directory.
+    Batch(BatchArguments),
+    /// Show a scene file in a window, where control points can be dragged and saved back.
+    Preview(PreviewArguments),
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArguments {
+    /// Scene files or glob patterns of them. Their formats are told by their extensions.
+    #[arg(required = true)]
+    pub inputs: Vec<String>,
+    /// Format of the images, which are named after the scene files with its extension.
+    #[arg(long, value_enum)]
+    pub format: Format,
+    /// Write the images into this directory instead of next to the scene files.
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+    /// How many scene files to render at once. Defaults to the available parallelism.
+    #[arg(long)]
+    pub jobs: Option<NonZeroUsize>,
+    #[command(flatten)]
+    pub render: RenderArguments,
+}
+
+#[derive(Debug, Args)]
+pub struct PreviewArguments {
+    /// The scene file, whose format is told by its extension.
+    pub input: PathBuf,
+    /// Save the edited scene to this path instead of overwriting the input. Its format is told by
+    /// its extension.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+    /// Which graphics api to draw the window with. `fallback` forces a software adapter.
+    #[arg(long, value_enum, default_value_t = Backend::Auto)]
+    pub backend: Backend,
+}
+
+#[derive(Debug, Clone, Copy, Args)]
+pub struct RenderArguments {
+    /// Where to rasterize curves. `auto` falls back to the cpu when no gpu adapter is available.
+    #[arg(long, value_enum, default_value_t = RendererKind::Auto)]
+    pub renderer: RendererKind,
+    /// Which graphics api to draw with on the gpu. `fallback` forces a software adapter.
+    #[arg(long, value_enum, default_value_t = Backend::Auto)]
+    pub backend: Backend,
 }
 
 impl Arguments {
     pub fn parse() -> Result<Result<Self, Error>> {
-        match Parser::try_parse() {
-            Result::Ok(args) => {
+        match <Self as Parser>::try_parse() {
+            Result::Ok(mut args) => {
                 info!("{args:?}");
+                args.sort_formats()?;
                 Result::Ok(Result::Ok(args))
             },
             Result::Err(error) => match error.kind() {
@@ -32,17 +126,51 @@ impl Arguments {
             },
         }
     }
+
+    // a lone positional format is that of the scene or the image depending on which one it names
+    fn sort_formats(&mut self) -> Result<()> {
+        match self.formats[..] {
+            [] => (),
+            [FormatArgument::Scene(input)] => self.input_format = Option::Some(input),
+            [FormatArgument::Image(output)] => self.output_format = Option::Some(output),
+            [FormatArgument::Scene(input), FormatArgument::Image(output)] => {
+                self.input_format = Option::Some(input);
+                self.output_format = Option::Some(output);
+            },
+            _ => bail!("need a scene format and then an image format as positional arguments"),
+        }
+
+        Result::Ok(())
+    }
 }
 
-#[derive(Debug, Clone, ValueEnum)]
-pub enum Input {
-    Json,
-    Toml,
+/// A positional format, which is either that of a scene or that of an image since their names
+/// never overlap.
+#[derive(Debug, Clone, Copy)]
+enum FormatArgument {
+    Scene(SceneFormat),
+    Image(Format),
 }
 
-#[derive(Debug, Clone, ValueEnum)]
-#[clap(rename_all = "lower")]
-pub enum Output {
-    Png,
-    WebP,
+impl ValueEnum for FormatArgument {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Scene(SceneFormat::Json),
+            Self::Scene(SceneFormat::Toml),
+            Self::Scene(SceneFormat::Yaml),
+            Self::Scene(SceneFormat::Ron),
+            Self::Image(Format::Png),
+            Self::Image(Format::WebP),
+            Self::Image(Format::Svg),
+            Self::Image(Format::Pdf),
+            Self::Image(Format::Eps),
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Self::Scene(format) => format.to_possible_value(),
+            Self::Image(format) => format.to_possible_value(),
+        }
+    }
 }
@@ -0,0 +1,220 @@
+use {
+    crate::{
+        args::BatchArguments,
+        job::Job,
+        status::StatusCode,
+    },
+    eyre::{
+        bail,
+        Context as _,
+        Report,
+        Result,
+    },
+    glob::glob,
+    std::{
+        collections::HashMap,
+        fs::create_dir_all,
+        num::NonZeroUsize,
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        thread::{
+            available_parallelism,
+            scope,
+        },
+    },
+    tracing::{
+        error,
+        info,
+    },
+    utokyo_fsc_is4029l1_assignment_m1::{
+        Format,
+        Renderer,
+        SceneFormat,
+    },
+};
+
+/// Renders every scene file of `args` on as many threads as jobs, printing a line for each file.
+///
+/// The status is that of the first file which failed in the order of the inputs.
+pub fn run(args: BatchArguments) -> StatusCode {
+    let inputs = match expand(&args.inputs) {
+        Result::Ok(inputs) => inputs,
+        Result::Err(error) => {
+            error!("{error:?}");
+            return StatusCode::Input;
+        },
+    };
+
+    let outputs = match to_output_paths(&inputs, &args) {
+        Result::Ok(outputs) => outputs,
+        Result::Err(error) => {
+            error!("{error:?}");
+            return StatusCode::Input;
+        },
+    };
+
+    if let Option::Some(out_dir) = &args.out_dir
+        && let Result::Err(error) = create_dir_all(out_dir)
+    {
+        error!("cannot create {}: {error}", out_dir.display());
+        return StatusCode::Io;
+    }
+
+    let jobs = args
+        .jobs
+        .or_else(|| available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+        .min(inputs.len());
+
+    info!("render {} scene files with {} jobs", inputs.len(), jobs);
+    let next = AtomicUsize::new(0);
+
+    let statuses = scope(|scope| {
+        let workers = (0..jobs)
+            .map(|_| scope.spawn(|| work(&inputs, &outputs, &next, &args)))
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join())
+            .collect::<Result<Vec<_>, _>>()
+    });
+
+    // a panic has already been reported by the hook
+    let Result::Ok(statuses) = statuses else {
+        return StatusCode::Eyre;
+    };
+
+    let mut statuses = statuses.concat();
+    statuses.sort_by_key(|(i, _)| *i);
+
+    statuses
+        .into_iter()
+        .map(|(_, status)| status)
+        .find(|status| !matches!(status, StatusCode::Ok))
+        .unwrap_or(StatusCode::Ok)
+}
+
+// takes the next input until none is left, and each worker has its own gpu context so that they
+// never wait for each other
+fn work(
+    inputs: &[PathBuf],
+    outputs: &[PathBuf],
+    next: &AtomicUsize,
+    args: &BatchArguments,
+) -> Vec<(usize, StatusCode)> {
+    let mut renderer = Renderer::new(args.render.renderer, args.render.backend);
+    let mut statuses = Vec::new();
+
+    loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+
+        let Option::Some(input) = inputs.get(i) else {
+            break statuses;
+        };
+
+        let output = &outputs[i];
+
+        let status = match render_file(input, output, args.format, &mut renderer) {
+            Result::Ok(()) => {
+                println!(
+                    "{:?}: {} -> {}",
+                    StatusCode::Ok,
+                    input.display(),
+                    output.display()
+                );
+                StatusCode::Ok
+            },
+            Result::Err((status, error)) => {
+                error!("{error:?}");
+
+                // snippets of diagnostics are only logged to keep a line for each file
+                let causes = error
+                    .chain()
+                    .filter_map(|cause| cause.to_string().lines().next().map(str::to_owned))
+                    .collect::<Vec<_>>();
+
+                println!("{status:?}: {}: {}", input.display(), causes.join(": "));
+                status
+            },
+        };
+
+        statuses.push((i, status));
+    }
+}
+
+// a pattern which matches nothing is kept as a path so that it is reported as a missing file
+fn expand(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut inputs = Vec::new();
+
+    for pattern in patterns {
+        let paths = glob(pattern)
+            .wrap_err_with(|| format!("{pattern} is invalid as a glob pattern"))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match paths.is_empty() {
+            true => inputs.push(PathBuf::from(pattern)),
+            false => inputs.extend(paths),
+        }
+    }
+
+    Result::Ok(inputs)
+}
+
+// workers write their images at the same time, so two inputs which would be written to the same
+// image are rejected before any of them is rendered
+fn to_output_paths(inputs: &[PathBuf], args: &BatchArguments) -> Result<Vec<PathBuf>> {
+    let outputs = inputs
+        .iter()
+        .map(|input| to_output_path(input, args))
+        .collect::<Vec<_>>();
+
+    let mut written = HashMap::new();
+
+    for (input, output) in inputs.iter().zip(&outputs) {
+        if let Option::Some(other) = written.insert(output, input) {
+            bail!(
+                "cannot write both {} and {} to {}",
+                other.display(),
+                input.display(),
+                output.display()
+            );
+        }
+    }
+
+    Result::Ok(outputs)
+}
+
+fn to_output_path(input: &Path, args: &BatchArguments) -> PathBuf {
+    let directory = match &args.out_dir {
+        Option::Some(out_dir) => out_dir,
+        Option::None => input.parent().unwrap_or(Path::new("")),
+    };
+
+    let mut name = input.file_stem().unwrap_or(input.as_os_str()).to_owned();
+    name.push(".");
+    name.push(args.format.extension());
+    directory.join(name)
+}
+
+fn render_file(
+    input: &Path,
+    output: &Path,
+    format: Format,
+    renderer: &mut Renderer,
+) -> Result<(), (StatusCode, Report)> {
+    Job {
+        input: Option::Some(input),
+        input_format: SceneFormat::from_path(input).map_err(|error| (StatusCode::Input, error))?,
+        output: Option::Some(output),
+        output_format: format,
+        summary: Option::None,
+    }
+    .run(renderer)
+}
@@ -1,10 +1,26 @@
 use {
-    crate::input::{
-        BezierMode,
-        CatmullRomMode,
-        Shape,
+    crate::{
+        input::{
+            BezierMode,
+            CatmullRomMode,
+            Density,
+            Endpoints,
+            KnotMode,
+            Knots,
+            PerPoint,
+            Sampling,
+            Shape,
+            Spacing,
+            TangentMode,
+            Tangents,
+        },
+        path::{
+            Command,
+            Path,
+        },
     },
     eyre::{
+        bail,
         ensure,
         Result,
     },
@@ -18,97 +34,991 @@ use {
     },
 };
 
-pub fn to_line_strip(shape: Shape) -> Result<Vec<[f32; 2]>> {
+pub fn to_line_strip(shape: &Shape) -> Result<Vec<[f32; 2]>> {
     match shape {
         Shape::Lines {
             points,
-        } => Result::Ok(points),
+            closed,
+        } => Result::Ok(match closed {
+            true => points.iter().chain(points.first()).copied().collect(),
+            false => points.clone(),
+        }),
         Shape::Bezier {
             points,
-            samples,
+            closed,
+            sampling,
             mode,
-        } => match mode {
-            BezierMode::Normal => bezier::<NormalBezierFn>(points, samples),
-            BezierMode::DeCasteljau => bezier::<DeCasteljauBezierFn>(points, samples),
+        } => {
+            let mut line_strip = match mode {
+                BezierMode::Normal => bezier::<NormalBezierFn>(points, *sampling)?,
+                BezierMode::DeCasteljau => bezier::<DeCasteljauBezierFn>(points, *sampling)?,
+            };
+
+            if *closed && let Option::Some(first) = line_strip.first() {
+                line_strip.push(*first);
+            }
+
+            Result::Ok(line_strip)
         },
         Shape::CatmullRom {
             points,
-            samples,
+            closed,
+            endpoints,
+            tension,
+            sampling,
             mode,
         } => {
             ensure!(
-                points.len() >= 4,
-                "need at least four points to draw a catmull rom spline"
+                mode.alpha().is_finite(),
+                "{} is invalid as an alpha of a catmull rom spline",
+                mode.alpha()
             );
 
-            Result::Ok(
-                points
-                    .windows(4)
-                    .flat_map(|ps| {
-                        let ps = new_array::<_, 4, _>(|i| Vector2::new(ps[i][0], ps[i][1]));
+            ensure!(
+                tension.is_finite(),
+                "{} is invalid as a tension of a catmull rom spline",
+                tension
+            );
 
-                        let is = new_array::<_, 3, _>(|i| match mode {
-                            CatmullRomMode::Uniform => 1.0,
-                            CatmullRomMode::Chordal => (ps[i + 1] - ps[i]).norm(),
-                            CatmullRomMode::Centripetal => {
-                                (ps[i + 1] - ps[i]).norm_squared().powf(0.25)
-                            },
-                        });
+            match (closed, endpoints) {
+                (true, _) => ensure!(
+                    points.len() >= 3,
+                    "need at least three points to draw a closed catmull rom spline"
+                ),
+                (false, Endpoints::Skip) => ensure!(
+                    points.len() >= 4,
+                    "need at least four points to draw a catmull rom spline"
+                ),
+                (false, _) => ensure!(
+                    points.len() >= 2,
+                    "need at least two points to draw a catmull rom spline through its endpoints"
+                ),
+            }
 
-                        let ts = new_array::<f32, 4, _>(|i| is[0..i].iter().sum());
+            let line_strip = sample(
+                catmull_rom_windows(points, *closed, *endpoints)
+                    .into_iter()
+                    .map(|ps| {
+                        let span = catmull_rom_span(&ps, mode, *tension);
+                        move |u| cubic_bezier(span, u)
+                    }),
+                *sampling,
+            )?;
 
-                        (0..samples).map(move |i| {
-                            let t = ts[1] + is[1] * (i as f32 / samples as f32);
+            Result::Ok(seal(line_strip, *closed))
+        },
+        Shape::KochanekBartels {
+            points,
+            closed,
+            endpoints,
+            tension,
+            continuity,
+            bias,
+            sampling,
+        } => {
+            let line_strip = sample(
+                kochanek_bartels_spans(points, *closed, *endpoints, [tension, continuity, bias])?
+                    .into_iter()
+                    .map(|span| move |u| cubic_bezier(span, u)),
+                *sampling,
+            )?;
 
-                            let r#as = new_array::<_, 3, _>(|i| {
-                                let r = (t - ts[i]) / is[i];
-                                (1.0 - r) * ps[i] + r * ps[i + 1]
-                            });
+            Result::Ok(seal(line_strip, *closed))
+        },
+        Shape::Hermite {
+            points,
+            closed,
+            tangents,
+            tension,
+            sampling,
+        } => {
+            let line_strip = sample(
+                hermite_spans(points, *closed, tangents, *tension)?
+                    .into_iter()
+                    .map(|span| move |u| cubic_bezier(span, u)),
+                *sampling,
+            )?;
 
-                            let bs = new_array::<_, 2, _>(|i| {
-                                let r = (t - ts[i]) / (is[i] + is[i + 1]);
-                                (1.0 - r) * r#as[i] + r * r#as[i + 1]
-                            });
+            Result::Ok(seal(line_strip, *closed))
+        },
+        Shape::BSpline {
+            degree,
+            points,
+            closed,
+            knots,
+            sampling,
+        } => b_spline(
+            *degree,
+            points
+                .iter()
+                .map(|point| Vector3::new(point[0], point[1], 1.0))
+                .collect(),
+            *closed,
+            knots,
+            *sampling,
+        ),
+        Shape::Nurbs {
+            degree,
+            points,
+            closed,
+            knots,
+            sampling,
+        } => b_spline(
+            *degree,
+            to_homogeneous(points, false)?,
+            *closed,
+            knots,
+            *sampling,
+        ),
+    }
+}
 
-                            let cs = new_array::<_, 1, _>(|i| {
-                                let r = (t - ts[i + 1]) / is[i + 1];
-                                (1.0 - r) * bs[i] + r * bs[i + 1]
-                            });
+/// Converts a shape into an exactly equivalent [`Path`], if there is one.
+pub fn to_path(shape: &Shape) -> Option<Path> {
+    let path = match shape {
+        Shape::Lines {
+            points, ..
+        } => Path::polyline(points),
+        Shape::Bezier {
+            points, ..
+        } => {
+            // a polynomial bezier curve is a rational one whose weights are all the same
+            let weight = points.first()?[2];
+
+            if weight == 0.0 || points.iter().any(|point| point[2] != weight) {
+                return Option::None;
+            }
+
+            let ps = points
+                .iter()
+                .map(|point| [point[0], point[1]])
+                .collect::<Vec<_>>();
 
-                            cs[0].into()
-                        })
-                    })
-                    .chain(once(points[points.len() - 2]))
-                    .collect(),
-            )
+            match ps[..] {
+                [_] => Path::polyline(&ps),
+                [p0, p1] => Path(vec![Command::Move(p0), Command::Line(p1)]),
+                [p0, p1, p2] => Path(vec![Command::Move(p0), Command::Quadratic(p1, p2)]),
+                [p0, p1, p2, p3] => Path(vec![Command::Move(p0), Command::Cubic(p1, p2, p3)]),
+                _ => return Option::None,
+            }
         },
+        Shape::CatmullRom {
+            points,
+            closed,
+            endpoints,
+            tension,
+            mode,
+            ..
+        } => to_cubic_path(
+            catmull_rom_windows(points, *closed, *endpoints)
+                .iter()
+                .map(|ps| catmull_rom_span(ps, mode, *tension)),
+        )?,
+        Shape::KochanekBartels {
+            points,
+            closed,
+            endpoints,
+            tension,
+            continuity,
+            bias,
+            ..
+        } => to_cubic_path(
+            kochanek_bartels_spans(points, *closed, *endpoints, [tension, continuity, bias])
+                .ok()?,
+        )?,
+        Shape::Hermite {
+            points,
+            closed,
+            tangents,
+            tension,
+            ..
+        } => to_cubic_path(hermite_spans(points, *closed, tangents, *tension).ok()?)?,
+        Shape::BSpline {
+            ..
+        }
+        | Shape::Nurbs {
+            ..
+        } => return Option::None,
+    };
+
+    Option::Some(match shape.is_closed() {
+        true => path.close(),
+        false => path,
+    })
+}
+
+// cubic bezier curves connected end to end, which are only a path if all of them are finite
+fn to_cubic_path(spans: impl IntoIterator<Item = [Vector2<f32>; 4]>) -> Option<Path> {
+    let mut commands = Vec::new();
+
+    for [p0, p1, p2, p3] in spans {
+        if commands.is_empty() {
+            commands.push(Command::Move(p0.into()));
+        }
+
+        if !p1.iter().chain(&p2).all(|x| x.is_finite()) {
+            return Option::None;
+        }
+
+        commands.push(Command::Cubic(p1.into(), p2.into(), p3.into()));
     }
+
+    match commands.is_empty() {
+        true => Option::None,
+        false => Option::Some(Path(commands)),
+    }
+}
+
+fn cubic_bezier([p0, p1, p2, p3]: [Vector2<f32>; 4], t: f32) -> Vector2<f32> {
+    let s = 1.0 - t;
+    s * s * s * p0 + 3.0 * s * s * t * p1 + 3.0 * s * t * t * p2 + t * t * t * p3
 }
 
-fn bezier<F: BezierFn>(points: Vec<[f32; 3]>, samples: usize) -> Result<Vec<[f32; 2]>> {
+// the control points of the cubic bezier curves which are the same as the spans of a hermite
+// spline, whose control points are a third of the tangents times the width of the span away
+fn hermite_spans(
+    points: &[[f32; 2]],
+    closed: bool,
+    tangents: &Tangents,
+    tension: Option<f32>,
+) -> Result<Vec<[Vector2<f32>; 4]>> {
+    let n = points.len();
+
+    ensure!(n >= 2, "need at least two points to draw a hermite spline");
+
+    let tension = match (tangents, tension) {
+        (Tangents::Mode(TangentMode::Cardinal), tension) => tension.unwrap_or(0.0),
+        (_, Option::None) => 0.0,
+        (_, Option::Some(_)) => bail!("cannot give a tension to tangents which are not cardinal"),
+    };
+
+    ensure!(
+        tension.is_finite(),
+        "{} is invalid as a tension of a hermite spline",
+        tension
+    );
+
+    let ps = points
+        .iter()
+        .copied()
+        .map(Vector2::from)
+        .collect::<Vec<_>>();
+    let next = |i: usize| (i + 1) % n;
+
+    let span_count = match closed {
+        true => n,
+        false => n - 1,
+    };
+
+    // the widths of the spans in the parameter which the tangents are derivatives in
+    let mut widths = vec![1.0; span_count];
+
+    let ms: Vec<Vector2<f32>> = match tangents {
+        Tangents::Explicit(tangents) => {
+            ensure!(
+                tangents.len() == n,
+                "need {} tangents for a hermite spline with {} points but {} are given",
+                n,
+                n,
+                tangents.len()
+            );
+
+            tangents.iter().copied().map(Vector2::from).collect()
+        },
+        Tangents::Mode(mode @ (TangentMode::FiniteDifference | TangentMode::Cardinal)) => {
+            let scale = match mode {
+                TangentMode::Cardinal => 1.0 - tension,
+                _ => 1.0,
+            };
+
+            (0..n)
+                .map(|i| {
+                    scale
+                        * match (closed, i) {
+                            (true, _) => (ps[next(i)] - ps[(i + n - 1) % n]) / 2.0,
+                            (false, 0) => ps[1] - ps[0],
+                            (false, _) if i == n - 1 => ps[i] - ps[i - 1],
+                            (false, _) => (ps[i + 1] - ps[i - 1]) / 2.0,
+                        }
+                })
+                .collect()
+        },
+        Tangents::Mode(TangentMode::Monotone) => {
+            ensure!(!closed, "cannot close a monotone hermite spline");
+
+            widths = ps.windows(2).map(|ps| ps[1].x - ps[0].x).collect();
+
+            ensure!(
+                widths.iter().all(|h| *h > 0.0 && h.is_finite()),
+                "need x coordinates which strictly increase to draw a monotone hermite spline"
+            );
+
+            monotone_slopes(&ps, &widths)
+                .into_iter()
+                .map(|m| Vector2::new(1.0, m))
+                .collect()
+        },
+    };
+
+    Result::Ok(
+        (0..span_count)
+            .map(|i| {
+                let h = widths[i] / 3.0;
+                let j = next(i);
+                [ps[i], ps[i] + h * ms[i], ps[j] - h * ms[j], ps[j]]
+            })
+            .collect(),
+    )
+}
+
+// the slopes of the monotone cubic interpolation by fritsch and carlson, which start from averages
+// of the secants and are limited where they would overshoot
+fn monotone_slopes(ps: &[Vector2<f32>], widths: &[f32]) -> Vec<f32> {
+    let secants = ps
+        .windows(2)
+        .zip(widths)
+        .map(|(ps, h)| (ps[1].y - ps[0].y) / h)
+        .collect::<Vec<_>>();
+
+    let n = ps.len();
+
+    let mut ms = (0..n)
+        .map(|i| match i {
+            0 => secants[0],
+            _ if i == n - 1 => secants[n - 2],
+            _ if secants[i - 1] * secants[i] <= 0.0 => 0.0,
+            _ => (secants[i - 1] + secants[i]) / 2.0,
+        })
+        .collect::<Vec<_>>();
+
+    for (i, secant) in secants.iter().enumerate() {
+        match *secant == 0.0 {
+            true => [ms[i], ms[i + 1]] = [0.0; 2],
+            false => {
+                let [a, b] = [ms[i], ms[i + 1]].map(|m| m / secant);
+                let r = a.hypot(b);
+
+                if r > 3.0 {
+                    [ms[i], ms[i + 1]] = [a, b].map(|x| 3.0 / r * x * secant);
+                }
+            },
+        }
+    }
+
+    ms
+}
+
+// the four points around each span, which wrap around the ends of a closed spline so that every
+// point starts a span, and which are extended by phantom points at the ends of an open one unless
+// they are skipped
+fn catmull_rom_windows(
+    points: &[[f32; 2]],
+    closed: bool,
+    endpoints: Endpoints,
+) -> Vec<[Vector2<f32>; 4]> {
+    let n = points.len();
+    let ps = points
+        .iter()
+        .copied()
+        .map(Vector2::from)
+        .collect::<Vec<_>>();
+
+    let ps = match (closed, endpoints, n) {
+        (true, _, 3..) => (0..n + 3).map(|i| ps[(i + n - 1) % n]).collect(),
+        (true, ..) | (false, _, 0..2) => Vec::new(),
+        (false, Endpoints::Skip, _) => ps,
+        (false, _, _) => {
+            let rev = ps.iter().rev().copied().collect::<Vec<_>>();
+            let [first, last] = [&ps, &rev].map(|ps| to_phantom(ps, endpoints));
+            once(first).chain(ps).chain(once(last)).collect()
+        },
+    };
+
+    ps.windows(4).map(|ps| new_array(|i| ps[i])).collect()
+}
+
+// the point before `ps[0]` when `ps` has at least two points
+fn to_phantom(ps: &[Vector2<f32>], endpoints: Endpoints) -> Vector2<f32> {
+    match (endpoints, ps) {
+        (Endpoints::Extrapolate, [p0, p1, p2, ..]) => 3.0 * p0 - 3.0 * p1 + p2,
+        (Endpoints::Duplicate, [p0, ..]) => *p0,
+        (_, [p0, p1, ..]) => 2.0 * p0 - p1,
+        (_, _) => ps[0],
+    }
+}
+
+// a closed line strip ends exactly at its start, which the last sample only nearly reaches
+fn seal(mut line_strip: Vec<[f32; 2]>, closed: bool) -> Vec<[f32; 2]> {
+    if closed && let [first, .., last] = &mut line_strip[..] {
+        *last = *first;
+    }
+
+    line_strip
+}
+
+// the control points of the cubic bezier curve which is the same as the span from `ps[1]` to
+// `ps[2]`, since the span is a cubic whose tangents at both ends are known and the control points
+// are one third of them away, which `tension` shortens
+fn catmull_rom_span(
+    ps: &[Vector2<f32>; 4],
+    mode: &CatmullRomMode,
+    tension: f32,
+) -> [Vector2<f32>; 4] {
+    let is = catmull_rom_intervals(ps, mode);
+
+    let tangent = |i: usize| {
+        (ps[i] - ps[i - 1]) / is[i - 1] - (ps[i + 1] - ps[i - 1]) / (is[i - 1] + is[i])
+            + (ps[i + 1] - ps[i]) / is[i]
+    };
+
+    let scale = (1.0 - tension) * is[1] / 3.0;
+    [
+        ps[1],
+        ps[1] + tangent(1) * scale,
+        ps[2] - tangent(2) * scale,
+        ps[2],
+    ]
+}
+
+// the spans of a kochanek bartels spline, which is a uniform catmull rom spline whose tangents on
+// both sides of each point are bent by its tension, continuity and bias
+fn kochanek_bartels_spans(
+    points: &[[f32; 2]],
+    closed: bool,
+    endpoints: Endpoints,
+    parameters: [&PerPoint; 3],
+) -> Result<Vec<[Vector2<f32>; 4]>> {
+    let n = points.len();
+
+    match (closed, endpoints) {
+        (true, _) => ensure!(
+            n >= 3,
+            "need at least three points to draw a closed kochanek bartels spline"
+        ),
+        (false, Endpoints::Skip) => ensure!(
+            n >= 4,
+            "need at least four points to draw a kochanek bartels spline"
+        ),
+        (false, _) => ensure!(
+            n >= 2,
+            "need at least two points to draw a kochanek bartels spline through its endpoints"
+        ),
+    }
+
+    for (name, parameter) in ["tension", "continuity", "bias"]
+        .into_iter()
+        .zip(parameters)
+    {
+        match parameter {
+            PerPoint::All(value) => ensure!(
+                value.is_finite(),
+                "{} is invalid as a {} of a kochanek bartels spline",
+                value,
+                name
+            ),
+            PerPoint::Each(values) => {
+                ensure!(
+                    values.len() == n,
+                    "need {} values of {} for a kochanek bartels spline but {} are given",
+                    n,
+                    name,
+                    values.len()
+                );
+
+                if let Option::Some(value) = values.iter().find(|value| !value.is_finite()) {
+                    bail!(
+                        "{} is invalid as a {} of a kochanek bartels spline",
+                        value,
+                        name
+                    );
+                }
+            },
+        }
+    }
+
+    // the windows of an open spline whose ends are skipped start a point earlier than their spans
+    let offset = match (closed, endpoints) {
+        (false, Endpoints::Skip) => 1,
+        _ => 0,
+    };
+
+    let [tension, continuity, bias] = parameters;
+
+    // the tangent at the `i`th point, which is `ps[1]` of the window, leaving it if `out` and
+    // entering it otherwise
+    let tangent = |ps: &[Vector2<f32>], i: usize, out: bool| {
+        let [t, c, b] = [tension, continuity, bias].map(|parameter| parameter.get(i));
+
+        let c = match out {
+            true => -c,
+            false => c,
+        };
+
+        (1.0 - t) / 2.0
+            * ((1.0 + b) * (1.0 + c) * (ps[1] - ps[0]) + (1.0 - b) * (1.0 - c) * (ps[2] - ps[1]))
+    };
+
+    Result::Ok(
+        catmull_rom_windows(points, closed, endpoints)
+            .iter()
+            .enumerate()
+            .map(|(k, ps)| {
+                let i = (k + offset) % n;
+                [
+                    ps[1],
+                    ps[1] + tangent(&ps[..3], i, true) / 3.0,
+                    ps[2] - tangent(&ps[1..], (i + 1) % n, false) / 3.0,
+                    ps[2],
+                ]
+            })
+            .collect(),
+    )
+}
+
+// lengths of the knot intervals between four consecutive control points, where an outer interval
+// between the same points such as duplicated endpoints takes the length of the middle one so that
+// the span stays defined
+fn catmull_rom_intervals(ps: &[Vector2<f32>; 4], mode: &CatmullRomMode) -> [f32; 3] {
+    let alpha = mode.alpha();
+    let is = new_array(|i| (ps[i + 1] - ps[i]).norm_squared().powf(alpha / 2.0));
+
+    is.map(|i| match i == 0.0 {
+        true => is[1],
+        false => i,
+    })
+}
+
+// `points` are in homogeneous coordinates like those of bezier curves, and a closed b spline is an
+// open one on the points followed by the first `degree` of them again, whose knot intervals repeat
+// as often as the points
+fn b_spline(
+    degree: usize,
+    points: Vec<Vector3<f32>>,
+    closed: bool,
+    knots: &Knots,
+    sampling: Sampling,
+) -> Result<Vec<[f32; 2]>> {
+    ensure!(
+        degree >= 1,
+        "need a degree of at least one to draw a b spline"
+    );
+
+    ensure!(
+        points.len() > degree,
+        "need at least {} points to draw a b spline of degree {}",
+        degree + 1,
+        degree
+    );
+
+    let explicit = matches!(knots, Knots::Explicit(_));
+
+    let knots = match (knots, closed) {
+        (Knots::Explicit(knots), _) => knots.clone(),
+        (Knots::Mode(mode), false) => to_knots(mode, degree, &points),
+        (Knots::Mode(mode), true) => to_periodic_knots(mode, degree, &points),
+    };
+
+    // a closed curve wraps around to its first `degree` points, which need knots of their own
+    let count = points.len();
+
+    let points = match closed {
+        true => points.iter().chain(&points[..degree]).copied().collect(),
+        false => points,
+    };
+
+    ensure!(
+        knots.len() == points.len() + degree + 1,
+        "need {} knots for a {} of degree {} with {} points but {} are given",
+        points.len() + degree + 1,
+        match closed {
+            true => "closed b spline",
+            false => "b spline",
+        },
+        degree,
+        count,
+        knots.len()
+    );
+
+    ensure!(
+        knots.iter().all(|knot| knot.is_finite()) && knots.is_sorted(),
+        "{:?} is invalid as knots, which must be finite and non-decreasing",
+        knots
+    );
+
+    // a closed curve only meets its start if the wrapped points have the same knot intervals as
+    // the points they repeat, up to rounding of the given knots
+    if closed && explicit {
+        let n = points.len() - degree;
+        let tolerance = 1e-4 * (knots[knots.len() - 1] - knots[0]);
+
+        ensure!(
+            (0..knots.len() - 1 - n).all(|j| {
+                let [a, b] = [j, j + n].map(|j| knots[j + 1] - knots[j]);
+                (a - b).abs() <= tolerance
+            }),
+            "{:?} is invalid as knots of a closed b spline, whose intervals must repeat every {} knots",
+            knots,
+            n
+        );
+    }
+
+    // the curve is defined on [knots[degree], knots[points.len()]], so each non-empty span in it
+    // is a polynomial piece
+    let spans = (degree..points.len())
+        .filter(|k| knots[*k] < knots[k + 1])
+        .collect::<Vec<_>>();
+
+    ensure!(
+        !spans.is_empty(),
+        "{:?} is invalid as knots because the domain is empty",
+        knots
+    );
+
+    let (points, knots) = (&points, &knots);
+
+    let line_strip = sample(
+        spans.iter().map(|k| {
+            let [t0, t1] = [knots[*k], knots[k + 1]];
+
+            move |u| {
+                let p = de_boor(degree, points, knots, *k, t0 + (t1 - t0) * u);
+                p.xy() / p.z
+            }
+        }),
+        sampling,
+    )?;
+
+    Result::Ok(seal(line_strip, closed))
+}
+
+// evaluates the piece on `knots[k]..knots[k + 1]` at `t`
+fn de_boor(
+    degree: usize,
+    points: &[Vector3<f32>],
+    knots: &[f32],
+    k: usize,
+    t: f32,
+) -> Vector3<f32> {
+    let mut ds = points[k - degree..=k].to_vec();
+
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + k - degree;
+            let a = (t - knots[i]) / (knots[i + 1 + degree - r] - knots[i]);
+            ds[j] = (1.0 - a) * ds[j - 1] + a * ds[j];
+        }
+    }
+
+    ds[degree]
+}
+
+fn to_knots(mode: &KnotMode, degree: usize, points: &[Vector3<f32>]) -> Vec<f32> {
+    let count = points.len() + degree + 1;
+
+    // parameters of the control points which are averaged into inner knots of clamped knots
+    let parameters = match mode {
+        KnotMode::Uniform => return (0..count).map(|i| i as f32).collect(),
+        KnotMode::Open => (0..points.len())
+            .map(|i| i as f32 / (points.len() - 1) as f32)
+            .collect::<Vec<_>>(),
+        KnotMode::ChordLength => {
+            let mut parameters = vec![0.0];
+
+            for ps in points.windows(2) {
+                let [p0, p1] = [ps[0], ps[1]].map(|p| p.xy() / p.z);
+                parameters.push(parameters[parameters.len() - 1] + (p1 - p0).norm());
+            }
+
+            let length = parameters[parameters.len() - 1];
+
+            match length > 0.0 && length.is_finite() {
+                true => parameters.into_iter().map(|u| u / length).collect(),
+                false => return to_knots(&KnotMode::Open, degree, points),
+            }
+        },
+    };
+
+    let mut knots = vec![0.0; degree + 1];
+
+    for j in 1..points.len() - degree {
+        knots.push(parameters[j..j + degree].iter().sum::<f32>() / degree as f32);
+    }
+
+    knots.resize(count, 1.0);
+    knots
+}
+
+// every interval is repeated after as many knots as `points`, where `Open` is the same as
+// `Uniform` because a closed curve has no ends to clamp
+fn to_periodic_knots(mode: &KnotMode, degree: usize, points: &[Vector3<f32>]) -> Vec<f32> {
+    let n = points.len();
+
+    let intervals = match mode {
+        KnotMode::Uniform | KnotMode::Open => vec![1.0; n],
+        KnotMode::ChordLength => {
+            let intervals = (0..n)
+                .map(|i| {
+                    let [p0, p1] = [points[i], points[(i + 1) % n]].map(|p| p.xy() / p.z);
+                    (p1 - p0).norm()
+                })
+                .collect::<Vec<_>>();
+
+            let length = intervals.iter().sum::<f32>();
+
+            match length > 0.0 && length.is_finite() {
+                true => intervals.into_iter().map(|l| l / length).collect(),
+                false => return to_periodic_knots(&KnotMode::Uniform, degree, points),
+            }
+        },
+    };
+
+    // the span of a knot interval is mostly between the two points in the middle of those which
+    // affect it
+    let mut knots = vec![0.0];
+
+    for j in 0..n + 2 * degree {
+        knots.push(knots[j] + intervals[(j + n - degree.div_ceil(2)) % n]);
+    }
+
+    knots
+}
+
+// weighted points as homogeneous coordinates, which are divided by their weights once they are
+// blended. only a bezier curve can have weights which are not positive between its ends, because
+// the weights at its ends are the only ones blended there
+fn to_homogeneous(points: &[[f32; 3]], bezier: bool) -> Result<Vec<Vector3<f32>>> {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, &[x, y, w])| {
+            let end = i == 0 || i == points.len() - 1;
+
+            ensure!(
+                w.is_finite() && (w > 0.0 || bezier && !end),
+                "{} is invalid as a weight of a curve",
+                w
+            );
+
+            Result::Ok(w * Vector3::new(x, y, 1.0))
+        })
+        .collect()
+}
+
+fn bezier<F: BezierFn>(points: &[[f32; 3]], sampling: Sampling) -> Result<Vec<[f32; 2]>> {
     ensure!(
         !points.is_empty(),
         "need at least one point to draw a bezier curve"
     );
 
     let mut f = F::new(points.len() - 1);
+    let ps = to_homogeneous(points, true)?;
+
+    // the whole curve is a single piece, and its samples include both of the end points
+    let sampling = match sampling.density {
+        Density::Samples {
+            samples,
+        } => Sampling {
+            density: Density::Samples {
+                samples: samples.saturating_sub(1),
+            },
+            ..sampling
+        },
+        _ => sampling,
+    };
+
+    let line_strip = sample(
+        once(|t| {
+            let p = f.call(t, ps.iter().copied());
+            p.xy() / p.z
+        }),
+        sampling,
+    )?;
+
+    ensure!(
+        line_strip.iter().flatten().all(|x| x.is_finite()),
+        "need weights which do not cancel out where a bezier curve is sampled"
+    );
+
+    Result::Ok(line_strip)
+}
+
+// takes points from pieces of a curve which are connected end to end and are each parameterized
+// over [0, 1]
+fn sample<F: FnMut(f32) -> Vector2<f32>>(
+    pieces: impl IntoIterator<Item = F>,
+    sampling: Sampling,
+) -> Result<Vec<[f32; 2]>> {
+    if let Density::Tolerance {
+        tolerance,
+    } = sampling.density
+    {
+        ensure!(
+            tolerance > 0.0 && tolerance.is_finite(),
+            "{} is invalid as a tolerance of a curve",
+            tolerance
+        );
+    }
+
+    let mut pieces = pieces.into_iter().collect::<Vec<_>>();
+
+    let points = match sampling.spacing {
+        Spacing::Parameter => {
+            let mut points = Vec::new();
+
+            for f in &mut pieces {
+                let p0 = match points.last() {
+                    Option::Some(p0) => *p0,
+                    Option::None => {
+                        let p0 = f(0.0);
+                        points.push(p0);
+                        p0
+                    },
+                };
+
+                match sampling.density {
+                    Density::Samples {
+                        samples,
+                    } => points.extend((1..=samples).map(|i| f(i as f32 / samples as f32))),
+                    Density::Tolerance {
+                        tolerance,
+                    } => {
+                        let mut ts = Vec::new();
+                        let p1 = f(1.0);
+                        subdivide(f, [0.0, 1.0], [p0, p1], tolerance, 0, &mut ts);
+                        points.extend(ts.into_iter().map(|(_, p)| p));
+                    },
+                }
+            }
+
+            points
+        },
+        Spacing::ArcLength => {
+            let Density::Samples {
+                samples,
+            } = sampling.density
+            else {
+                bail!("need samples instead of a tolerance to sample a curve by arc length");
+            };
+
+            ensure!(
+                sampling.accuracy > 0.0 && sampling.accuracy.is_finite(),
+                "{} is invalid as an accuracy of a curve",
+                sampling.accuracy
+            );
+
+            arc_length(&mut pieces, samples, sampling.accuracy)?
+        },
+    };
+
+    Result::Ok(points.into_iter().map(Into::into).collect())
+}
+
+// takes `samples` points per piece at even intervals of the length of the whole curve
+fn arc_length(
+    pieces: &mut [impl FnMut(f32) -> Vector2<f32>],
+    samples: usize,
+    accuracy: f32,
+) -> Result<Vec<Vector2<f32>>> {
+    let Option::Some(first) = pieces.first_mut() else {
+        return Result::Ok(Vec::new());
+    };
+
+    // lengths from the start of the curve to the points of a polyline which is close enough to it
+    // to be measured instead, along with where they are on the pieces
+    let mut p0 = first(0.0);
+    let mut table = vec![(0, 0.0, 0.0)];
+
+    for (i, f) in pieces.iter_mut().enumerate() {
+        let mut ts = Vec::new();
+        let ends = [f(0.0), f(1.0)];
+        subdivide(f, [0.0, 1.0], ends, accuracy, 0, &mut ts);
+
+        for (t, p) in ts {
+            let length = table[table.len() - 1].2 + (p - p0).norm();
+            table.push((i, t, length));
+            p0 = p;
+        }
+    }
+
+    let length = table[table.len() - 1].2;
+
+    ensure!(
+        length.is_finite(),
+        "need a finite length to sample a curve by arc length"
+    );
+
+    let count = samples * pieces.len();
 
     Result::Ok(
-        (0..samples)
-            .map(|i| {
-                let p = f.call(
-                    i as f32 / (samples - 1) as f32,
-                    points
-                        .iter()
-                        .map(|point| point[2] * Vector3::new(point[0], point[1], 1.0)),
-                );
+        (0..=count)
+            .map(|j| {
+                let s = match count {
+                    0 => 0.0,
+                    _ => length * j as f32 / count as f32,
+                };
+
+                let k = table
+                    .partition_point(|(_, _, l)| *l < s)
+                    .clamp(1, table.len() - 1);
+
+                let (i, t1, l1) = table[k];
+
+                // the previous entry is the start of the piece if it is on the one before
+                let (t0, l0) = match table[k - 1] {
+                    (h, t0, l0) if h == i => (t0, l0),
+                    (_, _, l0) => (0.0, l0),
+                };
 
-                (p.xy() / p.z).into()
+                let r = match l1 > l0 {
+                    true => ((s - l0) / (l1 - l0)).clamp(0.0, 1.0),
+                    false => 1.0,
+                };
+
+                pieces[i](t0 + (t1 - t0) * r)
             })
             .collect(),
     )
 }
 
+// appends parameters and points up to `p1` after `p0`, halving the piece until it is within
+// `tolerance` of its chord
+fn subdivide(
+    f: &mut impl FnMut(f32) -> Vector2<f32>,
+    [t0, t1]: [f32; 2],
+    [p0, p1]: [Vector2<f32>; 2],
+    tolerance: f32,
+    depth: u32,
+    points: &mut Vec<(f32, Vector2<f32>)>,
+) {
+    // a few levels are always subdivided because the middle of a piece can happen to be on its
+    // chord even if the rest is not, and the depth is limited for pieces which never get flat such
+    // as those going to infinity
+    const MIN_DEPTH: u32 = 3;
+    const MAX_DEPTH: u32 = 16;
+
+    let t = (t0 + t1) / 2.0;
+    let p = f(t);
+
+    match depth >= MIN_DEPTH && distance_to_segment(p, p0, p1) <= tolerance || depth >= MAX_DEPTH {
+        true => points.push((t1, p1)),
+        false => {
+            subdivide(f, [t0, t], [p0, p], tolerance, depth + 1, points);
+            subdivide(f, [t, t1], [p, p1], tolerance, depth + 1, points);
+        },
+    }
+}
+
+fn distance_to_segment(p: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    let ab = b - a;
+
+    let r = match ab.norm_squared() > 0.0 {
+        true => ((p - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+
+    (p - (a + r * ab)).norm()
+}
+
 trait BezierFn {
     fn new(n: usize) -> Self;
     fn call(&mut self, t: f32, ps: impl Iterator<Item = Vector3<f32>>) -> Vector3<f32>;
@@ -162,7 +1072,7 @@ impl BezierFn for DeCasteljauBezierFn {
 
         while self.ps.len() > 1 {
             for i in 0..self.ps.len() - 1 {
-                self.ps[i] = t * self.ps[i] + (1.0 - t) * self.ps[i + 1];
+                self.ps[i] = (1.0 - t) * self.ps[i] + t * self.ps[i + 1];
             }
 
             self.ps.pop();
@@ -0,0 +1,342 @@
+use {
+    crate::{
+        input::{
+            Scene,
+            SceneFormat,
+        },
+        output::Drawing,
+    },
+    eyre::Result,
+    serde::Deserialize,
+    serde_json::{
+        from_str as json_from_str,
+        value::RawValue,
+    },
+    std::{
+        fmt::{
+            self,
+            Display,
+            Formatter,
+        },
+        io::Read,
+        ops::Range,
+    },
+    toml::{
+        from_str as toml_from_str,
+        Spanned,
+    },
+};
+
+/// How many lines of a curve are shown at most in a diagnostic.
+const MAX_LINES: usize = 6;
+
+/// The text of a scene file, which points errors of its scene at the lines they come from.
+///
+/// Lines and columns are found in every format, while curves are only found in JSON and TOML.
+pub struct SceneFile {
+    name: String,
+    format: SceneFormat,
+    text: String,
+}
+
+impl SceneFile {
+    /// Reads the whole text of a scene written in `format` from `reader`, where `name` tells
+    /// diagnostics which file it is.
+    pub fn read(
+        name: impl Into<String>,
+        format: SceneFormat,
+        mut reader: impl Read,
+    ) -> Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        Result::Ok(Self {
+            name: name.into(),
+            format,
+            text,
+        })
+    }
+
+    /// Parses the scene, pointing a failure at its line and column and the curve it is in.
+    pub fn scene(&self) -> Result<Scene> {
+        Scene::parse(self.format, &self.text).map_err(|(error, span)| match span {
+            Option::Some(span) => {
+                let curve = self.outline().and_then(|outline| {
+                    outline
+                        .into_iter()
+                        .enumerate()
+                        .find(|(_, (curve_span, _))| curve_span.contains(&span.start))
+                        .map(|(i, (_, kind))| (i, kind))
+                });
+
+                error.wrap_err(self.diagnose(span, curve))
+            },
+            Option::None => error,
+        })
+    }
+
+    /// Samples every curve of `scene`, which is parsed from this file, pointing a failure at the
+    /// curve which is invalid.
+    pub fn drawing(&self, scene: &Scene) -> Result<Drawing> {
+        Drawing::new(scene).map_err(|error| {
+            let span = error
+                .downcast_ref::<InvalidCurve>()
+                .and_then(|curve| Option::Some(self.outline()?.get(curve.index)?.0.clone()));
+
+            match span {
+                Option::Some(span) => error.wrap_err(self.diagnose(span, Option::None)),
+                Option::None => error,
+            }
+        })
+    }
+
+    // the span and kind of every curve, which is only known if the file is valid as its format
+    fn outline(&self) -> Option<Vec<(Range<usize>, Option<String>)>> {
+        match self.format {
+            SceneFormat::Json => {
+                let outline = json_from_str::<Outline<&RawValue>>(&self.text).ok()?;
+
+                Option::Some(
+                    outline
+                        .curve
+                        .into_iter()
+                        .map(|curve| {
+                            let start = curve.get().as_ptr() as usize - self.text.as_ptr() as usize;
+                            let kind = json_from_str::<Kind>(curve.get()).ok();
+                            (
+                                start..start + curve.get().len(),
+                                kind.and_then(|kind| kind.kind),
+                            )
+                        })
+                        .collect(),
+                )
+            },
+            SceneFormat::Toml => {
+                let outline = toml_from_str::<Outline<Spanned<Kind>>>(&self.text).ok()?;
+
+                Option::Some(
+                    outline
+                        .curve
+                        .into_iter()
+                        .map(|curve| (curve.span(), curve.into_inner().kind))
+                        .collect(),
+                )
+            },
+            SceneFormat::Yaml | SceneFormat::Ron => Option::None,
+        }
+    }
+
+    fn diagnose(&self, span: Range<usize>, curve: Option<(usize, Option<String>)>) -> Diagnostic {
+        let [line, column] = to_position(&self.text, span.start);
+
+        Diagnostic {
+            name: self.name.clone(),
+            line,
+            column,
+            curve,
+            snippet: to_snippet(&self.text, span),
+        }
+    }
+}
+
+/// Which curve failed to be drawn, which [`Drawing::new`] attaches to its errors.
+#[derive(Debug)]
+pub struct InvalidCurve {
+    pub index: usize,
+    pub kind: &'static str,
+}
+
+impl Display for InvalidCurve {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "curve[{}] ({}) is invalid", self.index, self.kind)
+    }
+}
+
+// where an error comes from, which is attached to the error as its context
+#[derive(Debug)]
+struct Diagnostic {
+    name: String,
+    line: usize,
+    column: usize,
+    curve: Option<(usize, Option<String>)>,
+    snippet: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "at {}:{}:{}", self.name, self.line, self.column)?;
+
+        match &self.curve {
+            Option::Some((i, Option::Some(kind))) => write!(f, " in curve[{i}] ({kind})")?,
+            Option::Some((i, Option::None)) => write!(f, " in curve[{i}]")?,
+            Option::None => (),
+        }
+
+        write!(f, "\n{}", self.snippet)
+    }
+}
+
+// only what diagnostics need from a scene, which is parsed apart from it to find the curves
+#[derive(Deserialize)]
+struct Outline<C> {
+    #[serde(default = "Vec::new")]
+    curve: Vec<C>,
+}
+
+#[derive(Deserialize)]
+struct Kind {
+    kind: Option<String>,
+}
+
+/// Finds the byte offset of a line and column counted in characters from one.
+pub fn to_offset(text: &str, line: usize, column: usize) -> usize {
+    let start = to_line_start(text, line);
+    let rest = &text[start..];
+
+    start
+        + rest
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(rest.len(), |(i, _)| i)
+}
+
+/// Finds the byte offset of a line and column counted in bytes from one, which is moved back to the
+/// start of the character it falls in.
+pub fn to_byte_offset(text: &str, line: usize, column: usize) -> usize {
+    let mut offset = (to_line_start(text, line) + column.saturating_sub(1)).min(text.len());
+
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    offset
+}
+
+// the byte offset of a line counted from one
+fn to_line_start(text: &str, line: usize) -> usize {
+    text.split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum()
+}
+
+// the line and column of a byte offset counted in characters from one, where an offset within a
+// character is that of the character
+fn to_position(text: &str, offset: usize) -> [usize; 2] {
+    let offset = (0..=offset.min(text.len()))
+        .rev()
+        .find(|i| text.is_char_boundary(*i))
+        .unwrap_or(0);
+
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    [
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    ]
+}
+
+// the numbered lines of `span` like those of rustc, where a span within a line is underlined
+fn to_snippet(text: &str, span: Range<usize>) -> String {
+    let [first, column] = to_position(text, span.start);
+    let [last, _] = to_position(text, span.end.max(span.start + 1) - 1);
+    let shown = last.min(first + MAX_LINES - 1);
+    let width = shown.to_string().len();
+    let mut lines = vec![format!("{:width$} |", "")];
+
+    lines.extend(
+        text.lines()
+            .enumerate()
+            .take(shown)
+            .skip(first - 1)
+            .map(|(i, line)| format!("{:>width$} | {}", i + 1, line)),
+    );
+
+    lines.push(match (first == last, shown < last) {
+        (true, _) => {
+            let length = text.get(span).map_or(0, |span| span.chars().count());
+            format!(
+                "{:width$} | {}{}",
+                "",
+                " ".repeat(column - 1),
+                "^".repeat(length.max(1))
+            )
+        },
+        (false, true) => format!("{:width$} | ...", ""),
+        (false, false) => format!("{:width$} |", ""),
+    });
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            to_byte_offset,
+            to_offset,
+            to_position,
+            to_snippet,
+            SceneFile,
+        },
+        crate::input::SceneFormat,
+    };
+
+    // two bytes for each of the first characters and three for each of the next
+    const TEXT: &str = "ab\nαβ日本x\n";
+
+    #[test]
+    fn offsets_count_characters_or_bytes() {
+        assert_eq!(to_offset(TEXT, 1, 2), 1);
+        assert_eq!(to_offset(TEXT, 2, 3), 7);
+        assert_eq!(to_offset(TEXT, 2, 5), 13);
+        assert_eq!(to_byte_offset(TEXT, 2, 5), 7);
+        assert_eq!(to_byte_offset(TEXT, 2, 11), 13);
+    }
+
+    #[test]
+    fn byte_offsets_stay_on_characters() {
+        assert_eq!(to_byte_offset(TEXT, 2, 2), 3);
+        assert_eq!(to_byte_offset(TEXT, 2, 7), 7);
+        assert_eq!(to_byte_offset(TEXT, 9, 9), TEXT.len());
+    }
+
+    #[test]
+    fn positions_count_characters() {
+        assert_eq!(to_position(TEXT, 0), [1, 1]);
+        assert_eq!(to_position(TEXT, 7), [2, 3]);
+        assert_eq!(to_position(TEXT, 13), [2, 5]);
+        assert_eq!(to_position(TEXT, 12), [2, 4]);
+
+        for (line, length) in [(1, 2), (2, 5)] {
+            for column in 1..=length + 1 {
+                assert_eq!(
+                    to_position(TEXT, to_offset(TEXT, line, column)),
+                    [line, column]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn snippets_underline_characters() {
+        assert_eq!(to_snippet(TEXT, 7..13), "  |\n2 | αβ日本x\n  |   ^^");
+
+        assert_eq!(to_snippet(TEXT, 0..8), "  |\n1 | ab\n2 | αβ日本x\n  |");
+    }
+
+    #[test]
+    fn json_errors_after_multibyte_text_find_their_curve() {
+        let text = concat!(
+            "{\"canvas\": {\"size\": [8, 8]},\n",
+            "\"curve\": [{\"kind\": \"lines\", \"points\": [], \"note\": \"日本語\", \"color\": \"nope\"}]}\n",
+        );
+
+        let file = SceneFile::read("mb.json", SceneFormat::Json, text.as_bytes()).unwrap();
+        let error = file.scene().err().unwrap().to_string();
+
+        assert!(error.starts_with("at mb.json:2:"), "{error}");
+        assert!(error.contains("in curve[0] (lines)"), "{error}");
+        assert!(error.contains("^"), "{error}");
+    }
+}
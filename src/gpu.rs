@@ -0,0 +1,750 @@
+use {
+    crate::{
+        input::{
+            Antialias,
+            Canvas,
+        },
+        output::{
+            unpremultiply,
+            Backend,
+            LineStrip,
+        },
+        stroke::tessellate,
+    },
+    eyre::{
+        bail,
+        ensure,
+        Context as _,
+        OptionExt,
+        Result,
+    },
+    futures::executor::block_on,
+    image::ColorType,
+    std::{
+        array::from_fn as new_array,
+        collections::HashMap,
+        slice::from_raw_parts as new_slice,
+        sync::mpsc::channel,
+    },
+    tracing::{
+        error,
+        info,
+    },
+    wgpu::{
+        include_wgsl,
+        vertex_attr_array,
+        Adapter,
+        BackendOptions,
+        Backends,
+        BlendState,
+        Buffer,
+        BufferAddress,
+        BufferDescriptor,
+        BufferUsages,
+        Color,
+        ColorTargetState,
+        ColorWrites,
+        CommandEncoder,
+        CommandEncoderDescriptor,
+        CompareFunction,
+        DepthBiasState,
+        DepthStencilState,
+        Device,
+        DeviceDescriptor,
+        Dx12BackendOptions,
+        Dx12Compiler,
+        Extent3d,
+        Features,
+        FragmentState,
+        FrontFace,
+        GlBackendOptions,
+        GlFenceBehavior,
+        Gles3MinorVersion,
+        Instance,
+        InstanceDescriptor,
+        InstanceFlags,
+        LoadOp,
+        MapMode,
+        MemoryHints,
+        MultisampleState,
+        NoopBackendOptions,
+        Operations,
+        Origin3d,
+        PollType,
+        PolygonMode,
+        PowerPreference,
+        PrimitiveState,
+        PrimitiveTopology,
+        Queue,
+        RenderPassColorAttachment,
+        RenderPassDepthStencilAttachment,
+        RenderPassDescriptor,
+        RenderPipeline,
+        RenderPipelineDescriptor,
+        RequestAdapterOptions,
+        ShaderModule,
+        StencilFaceState,
+        StencilOperation,
+        StencilState,
+        StoreOp,
+        Surface,
+        SurfaceTarget,
+        TexelCopyBufferInfo,
+        TexelCopyBufferLayout,
+        TexelCopyTextureInfo,
+        Texture,
+        TextureAspect,
+        TextureDescriptor,
+        TextureDimension,
+        TextureFormat,
+        TextureUsages,
+        Trace,
+        VertexAttribute,
+        VertexBufferLayout,
+        VertexState,
+        VertexStepMode,
+        COPY_BUFFER_ALIGNMENT,
+        COPY_BYTES_PER_ROW_ALIGNMENT,
+        VERTEX_STRIDE_ALIGNMENT,
+    },
+};
+
+/// The format of the depth and stencil with which every sample is blended once for each curve.
+const DEPTH_STENCIL_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
+
+/// A device with everything needed to draw line strips on it, which is kept between images.
+///
+/// Pipelines are cached for each texture format and sample count, textures are reused while images
+/// have the same size, and buffers only grow.
+pub struct Gpu {
+    pub adapter: Adapter,
+    device: Device,
+    queue: Queue,
+    module: ShaderModule,
+    vertex_attributes: [VertexAttribute; 4],
+    vertex_size: BufferAddress,
+    pipelines: HashMap<(TextureFormat, u32), [RenderPipeline; 2]>,
+    texture: Option<Texture>,
+    multisampled_texture: Option<Texture>,
+    depth_stencil_texture: Option<Texture>,
+    vertex_buffer: Option<Buffer>,
+    texture_buffer: Option<Buffer>,
+}
+
+impl Gpu {
+    pub fn new(backend: Backend) -> Result<Self> {
+        let instance = new_instance(backend);
+        Self::from_adapter(request_adapter(&instance, backend, Option::None)?)
+    }
+
+    fn from_adapter(adapter: Adapter) -> Result<Self> {
+        let (device, queue) = block_on(adapter.request_device(&DeviceDescriptor {
+            label: Option::None,
+            // some adapters support 8x multisampling only with adapter specific format features
+            required_features: adapter.features()
+                & Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            required_limits: Default::default(),
+            memory_hints: MemoryHints::Performance,
+            trace: Trace::Off,
+        }))?;
+
+        info!("{device:?}");
+        info!("{queue:?}");
+        let module = device.create_shader_module(include_wgsl!("shader.wgsl"));
+        info!("{module:?}");
+
+        let vertex_attributes = vertex_attr_array![
+            0 => Float32x2,
+            1 => Float32x4,
+            2 => Float32x2,
+            3 => Float32x2,
+        ];
+
+        let vertex_size = vertex_attributes
+            .iter()
+            .map(|attribute| attribute.format.size() + attribute.offset)
+            .max()
+            .ok_or_eyre("cannot get a size of a vertex")?
+            .div_ceil(VERTEX_STRIDE_ALIGNMENT)
+            * VERTEX_STRIDE_ALIGNMENT;
+
+        Result::Ok(Self {
+            adapter,
+            device,
+            queue,
+            module,
+            vertex_attributes,
+            vertex_size,
+            pipelines: HashMap::new(),
+            texture: Option::None,
+            multisampled_texture: Option::None,
+            depth_stencil_texture: Option::None,
+            vertex_buffer: Option::None,
+            texture_buffer: Option::None,
+        })
+    }
+
+    /// Requests a device which can draw onto the window of `target`, along with its surface.
+    pub fn with_surface(
+        backend: Backend,
+        target: impl Into<SurfaceTarget<'static>>,
+    ) -> Result<(Self, Surface<'static>)> {
+        let instance = new_instance(backend);
+        let surface = instance.create_surface(target)?;
+        info!("{surface:?}");
+        let adapter = request_adapter(&instance, backend, Option::Some(&surface))?;
+        Result::Ok((Self::from_adapter(adapter)?, surface))
+    }
+
+    /// Configures `surface` to be `size` large in a format whose colors are written as they are,
+    /// just like images.
+    pub fn configure(&self, surface: &Surface, size: [u32; 2]) -> Result<()> {
+        let mut config = surface
+            .get_default_config(&self.adapter, size[0], size[1])
+            .ok_or_eyre("cannot draw onto a surface with the adapter")?;
+
+        let formats = surface.get_capabilities(&self.adapter).formats;
+
+        if let Option::Some(format) = formats.into_iter().find(|format| !format.is_srgb()) {
+            config.format = format;
+        }
+
+        info!("{config:?}");
+        surface.configure(&self.device, &config);
+        Result::Ok(())
+    }
+
+    pub fn render(
+        &mut self,
+        color_type: ColorType,
+        canvas: &Canvas,
+        line_strips: &[LineStrip],
+    ) -> Result<Vec<u8>> {
+        let texture_format = match color_type {
+            ColorType::L8 => TextureFormat::R8Unorm,
+            ColorType::La8 => TextureFormat::Rg8Unorm,
+            ColorType::Rgba8 => TextureFormat::Rgba8Unorm,
+            ColorType::L16 => TextureFormat::R16Unorm,
+            ColorType::La16 => TextureFormat::Rg16Unorm,
+            ColorType::Rgba16 => TextureFormat::Rgba16Unorm,
+            _ => bail!("{:?} is not supported", color_type),
+        };
+
+        let extent = Extent3d {
+            width: canvas.size[0],
+            height: canvas.size[1],
+            depth_or_array_layers: 1,
+        };
+
+        let texture = reuse(
+            &self.device,
+            &mut self.texture,
+            &TextureDescriptor {
+                label: Option::None,
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: texture_format,
+                usage: TextureUsages::COPY_SRC | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+        );
+
+        let block_size = texture_format
+            .block_copy_size(Option::None)
+            .ok_or_eyre("cannot calculate a block copy size")?;
+
+        let physical_size = extent.physical_size(texture_format);
+        let row_size = block_size * physical_size.width;
+        let row_count = physical_size.height * physical_size.depth_or_array_layers;
+        let row_alignment = COPY_BYTES_PER_ROW_ALIGNMENT;
+        let aligned_row_size = row_size.div_ceil(row_alignment) * row_alignment;
+        let texture_buffer_size = (aligned_row_size * row_count) as BufferAddress;
+
+        let texture_buffer = grow(
+            &self.device,
+            &mut self.texture_buffer,
+            texture_buffer_size,
+            BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Option::None,
+            });
+
+        info!("{encoder:?}");
+        self.draw(&mut encoder, &texture, canvas, line_strips)?;
+
+        encoder.copy_texture_to_buffer(
+            TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            TexelCopyBufferInfo {
+                buffer: &texture_buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Option::Some(aligned_row_size),
+                    rows_per_image: Option::None,
+                },
+            },
+            extent,
+        );
+
+        self.queue.submit([encoder.finish()]);
+        let texture_buffer_slice = texture_buffer.slice(..texture_buffer_size);
+        info!("{texture_buffer_slice:?}");
+        let (sender, receiver) = channel();
+
+        texture_buffer_slice.map_async(MapMode::Read, move |result| {
+            info!("receive a result of map_async");
+
+            if let Result::Err(error) = sender
+                .send(result)
+                .wrap_err("cannot send a result from a callback")
+            {
+                error!("{error:?}");
+            }
+        });
+
+        self.device.poll(PollType::Wait)?;
+        receiver.recv()??;
+        let mut image_data = Vec::with_capacity((row_size * row_count) as _);
+        let texture_buffer_view = texture_buffer_slice.get_mapped_range();
+        info!("{texture_buffer_view:?}");
+
+        for i in 0..row_count {
+            image_data.extend_from_slice(
+                &texture_buffer_view[(i * aligned_row_size) as usize..][..row_size as usize],
+            );
+        }
+
+        drop(texture_buffer_view);
+        texture_buffer.unmap();
+
+        // other color types have no alpha to divide by or are not blended as images store them
+        if let ColorType::Rgba8 = color_type {
+            unpremultiply(&mut image_data);
+        }
+
+        Result::Ok(image_data)
+    }
+
+    /// Draws onto `texture`, which is usually that of a surface, stretching the canvas over it.
+    pub fn render_to_texture(
+        &mut self,
+        texture: &Texture,
+        canvas: &Canvas,
+        line_strips: &[LineStrip],
+    ) -> Result<()> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Option::None,
+            });
+
+        info!("{encoder:?}");
+        self.draw(&mut encoder, texture, canvas, line_strips)?;
+        self.queue.submit([encoder.finish()]);
+        Result::Ok(())
+    }
+
+    // records a pass which clears `texture` and strokes `line_strips` onto it, where the triangles
+    // of each line strip are drawn twice to blend every sample they cover only once even though
+    // they overlap at joins and bends
+    fn draw(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        texture: &Texture,
+        canvas: &Canvas,
+        line_strips: &[LineStrip],
+    ) -> Result<()> {
+        let sample_count = canvas.antialias.sample_count();
+        let pipelines = self.pipelines(texture.format(), sample_count)?;
+
+        let triangles = line_strips
+            .iter()
+            .map(|line_strip| {
+                (
+                    tessellate(
+                        &line_strip.positions,
+                        &line_strip.stroke,
+                        canvas.antialias.feather(),
+                        line_strip.closed,
+                    ),
+                    line_strip.color.0,
+                    line_strip.stroke.width,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let vertex_count = triangles
+            .iter()
+            .map(|(vertices, ..)| vertices.len())
+            .sum::<usize>();
+
+        let vertex_size = self.vertex_size as usize;
+        let mut vertex_data = vec![0; vertex_count * vertex_size];
+
+        // each line strip has its own range of depths, which are nearer than those of the line
+        // strips before it so that its fragments are never hidden by them
+        let step = 1.0 / line_strips.len().max(1) as f32;
+
+        for (i, (vertex, color, width, depth)) in triangles
+            .iter()
+            .enumerate()
+            .flat_map(|(j, (vertices, color, width))| {
+                let depth = [1.0 - (j + 1) as f32 * step, step];
+                vertices
+                    .iter()
+                    .map(move |vertex| (vertex, color, width, depth))
+            })
+            .enumerate()
+        {
+            let (position, distance) = (vertex.position, vertex.distance);
+            let vertex = &mut vertex_data[i * vertex_size..][0..vertex_size];
+
+            write_attribute(
+                vertex,
+                &self.vertex_attributes[0],
+                &new_array::<_, 2, _>(|i| 2.0 * position[i] / canvas.size[i] as f32 - 1.0),
+            );
+
+            write_attribute(vertex, &self.vertex_attributes[1], color);
+
+            // a zero distance in a stroke of width one is always fully covered
+            write_attribute(
+                vertex,
+                &self.vertex_attributes[2],
+                &match canvas.antialias {
+                    Antialias::Coverage => [distance, *width],
+                    _ => [0.0, 1.0],
+                },
+            );
+
+            write_attribute(vertex, &self.vertex_attributes[3], &depth);
+        }
+
+        let vertex_buffer = grow(
+            &self.device,
+            &mut self.vertex_buffer,
+            vertex_data.len() as _,
+            BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        );
+
+        self.queue.write_buffer(&vertex_buffer, 0, &vertex_data);
+        let texture_view = texture.create_view(&Default::default());
+        info!("{texture_view:?}");
+
+        // multisampled passes draw into a separate texture which is resolved into `texture`
+        let multisampled_texture_view = match sample_count {
+            1 => Option::None,
+            _ => Option::Some(
+                reuse(
+                    &self.device,
+                    &mut self.multisampled_texture,
+                    &TextureDescriptor {
+                        label: Option::None,
+                        size: texture.size(),
+                        mip_level_count: 1,
+                        sample_count,
+                        dimension: TextureDimension::D2,
+                        format: texture.format(),
+                        usage: TextureUsages::RENDER_ATTACHMENT,
+                        view_formats: &[],
+                    },
+                )
+                .create_view(&Default::default()),
+            ),
+        };
+
+        info!("{multisampled_texture_view:?}");
+
+        let depth_stencil_texture_view = reuse(
+            &self.device,
+            &mut self.depth_stencil_texture,
+            &TextureDescriptor {
+                label: Option::None,
+                size: texture.size(),
+                mip_level_count: 1,
+                sample_count,
+                dimension: TextureDimension::D2,
+                format: DEPTH_STENCIL_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+        )
+        .create_view(&Default::default());
+
+        info!("{depth_stencil_texture_view:?}");
+
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Option::None,
+            color_attachments: &[Option::Some(RenderPassColorAttachment {
+                view: multisampled_texture_view.as_ref().unwrap_or(&texture_view),
+                resolve_target: multisampled_texture_view.as_ref().map(|_| &texture_view),
+                ops: Operations {
+                    load: LoadOp::Clear({
+                        let [r, g, b, a] = canvas.color.premultiplied().map(|x| x as f64);
+
+                        Color {
+                            r,
+                            g,
+                            b,
+                            a,
+                        }
+                    }),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Option::Some(RenderPassDepthStencilAttachment {
+                view: &depth_stencil_texture_view,
+                depth_ops: Option::Some(Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: StoreOp::Discard,
+                }),
+                stencil_ops: Option::Some(Operations {
+                    load: LoadOp::Clear(0),
+                    store: StoreOp::Discard,
+                }),
+            }),
+            timestamp_writes: Option::None,
+            occlusion_query_set: Option::None,
+        });
+
+        info!("{pass:?}");
+        pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        let mut start = 0;
+
+        for (vertices, ..) in &triangles {
+            let end = start + vertices.len() as u32;
+
+            for pipeline in &pipelines {
+                pass.set_pipeline(pipeline);
+                pass.draw(start..end, 0..1);
+            }
+
+            start = end;
+        }
+
+        Result::Ok(())
+    }
+
+    // the first pipeline only leaves the depth of the most covered fragment of a line strip in each
+    // sample, and the second one blends the fragment of that depth and marks the sample as blended
+    fn pipelines(
+        &mut self,
+        texture_format: TextureFormat,
+        sample_count: u32,
+    ) -> Result<[RenderPipeline; 2]> {
+        if let Option::Some(pipelines) = self.pipelines.get(&(texture_format, sample_count)) {
+            return Result::Ok(pipelines.clone());
+        }
+
+        for format in [texture_format, DEPTH_STENCIL_FORMAT] {
+            ensure!(
+                self.adapter
+                    .get_texture_format_features(format)
+                    .flags
+                    .sample_count_supported(sample_count),
+                "{} samples of {:?} are not supported by the adapter",
+                sample_count,
+                format
+            );
+        }
+
+        let pipelines = [
+            (
+                true,
+                CompareFunction::Less,
+                CompareFunction::Always,
+                StencilOperation::Zero,
+                ColorWrites::empty(),
+            ),
+            (
+                false,
+                CompareFunction::Equal,
+                CompareFunction::Equal,
+                StencilOperation::IncrementClamp,
+                ColorWrites::all(),
+            ),
+        ]
+        .map(
+            |(depth_write_enabled, depth_compare, stencil_compare, stencil_pass_op, write_mask)| {
+                let stencil_face = StencilFaceState {
+                    compare: stencil_compare,
+                    fail_op: StencilOperation::Keep,
+                    depth_fail_op: StencilOperation::Keep,
+                    pass_op: stencil_pass_op,
+                };
+
+                self.device
+                    .create_render_pipeline(&RenderPipelineDescriptor {
+                        label: Option::None,
+                        layout: Option::None,
+                        vertex: VertexState {
+                            module: &self.module,
+                            entry_point: Option::None,
+                            compilation_options: Default::default(),
+                            buffers: &[VertexBufferLayout {
+                                array_stride: self.vertex_size,
+                                step_mode: VertexStepMode::Vertex,
+                                attributes: &self.vertex_attributes,
+                            }],
+                        },
+                        primitive: PrimitiveState {
+                            topology: PrimitiveTopology::TriangleList,
+                            strip_index_format: Option::None,
+                            front_face: FrontFace::Ccw,
+                            cull_mode: Option::None,
+                            unclipped_depth: false,
+                            polygon_mode: PolygonMode::Fill,
+                            conservative: false,
+                        },
+                        depth_stencil: Option::Some(DepthStencilState {
+                            format: DEPTH_STENCIL_FORMAT,
+                            depth_write_enabled,
+                            depth_compare,
+                            stencil: StencilState {
+                                front: stencil_face,
+                                back: stencil_face,
+                                read_mask: !0,
+                                write_mask: !0,
+                            },
+                            bias: DepthBiasState::default(),
+                        }),
+                        multisample: MultisampleState {
+                            count: sample_count,
+                            mask: !0,
+                            alpha_to_coverage_enabled: false,
+                        },
+                        fragment: Option::Some(FragmentState {
+                            module: &self.module,
+                            entry_point: Option::None,
+                            compilation_options: Default::default(),
+                            targets: &[Option::Some(ColorTargetState {
+                                format: texture_format,
+                                blend: Option::Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                                write_mask,
+                            })],
+                        }),
+                        multiview: Option::None,
+                        cache: Option::None,
+                    })
+            },
+        );
+
+        info!("{pipelines:?}");
+
+        self.pipelines
+            .insert((texture_format, sample_count), pipelines.clone());
+
+        Result::Ok(pipelines)
+    }
+}
+
+fn new_instance(backend: Backend) -> Instance {
+    let instance = Instance::new(&InstanceDescriptor {
+        backends: match backend {
+            Backend::Auto | Backend::Fallback => Backends::all(),
+            Backend::Vulkan => Backends::VULKAN,
+            Backend::Gl => Backends::GL,
+            Backend::Metal => Backends::METAL,
+            Backend::Dx12 => Backends::DX12,
+        },
+        flags: match cfg!(debug_assertions) {
+            true => InstanceFlags::debugging(),
+            false => InstanceFlags::empty(),
+        },
+        backend_options: BackendOptions {
+            gl: GlBackendOptions {
+                gles_minor_version: Gles3MinorVersion::Automatic,
+                fence_behavior: GlFenceBehavior::Normal,
+            },
+            dx12: Dx12BackendOptions {
+                shader_compiler: Dx12Compiler::Fxc,
+            },
+            noop: NoopBackendOptions {
+                enable: false,
+            },
+        },
+    });
+
+    info!("{instance:?}");
+    instance
+}
+
+fn request_adapter(
+    instance: &Instance,
+    backend: Backend,
+    compatible_surface: Option<&Surface>,
+) -> Result<Adapter> {
+    let adapter = block_on(instance.request_adapter(&RequestAdapterOptions {
+        power_preference: PowerPreference::HighPerformance,
+        force_fallback_adapter: matches!(backend, Backend::Fallback),
+        compatible_surface,
+    }))?;
+
+    let adapter_info = adapter.get_info();
+
+    info!(
+        "choose {} ({:?}) on {}",
+        adapter_info.name, adapter_info.device_type, adapter_info.backend
+    );
+
+    info!("{adapter:?}");
+    Result::Ok(adapter)
+}
+
+// replaces `buffer` with a larger one if it cannot hold `size` bytes
+fn grow(
+    device: &Device,
+    buffer: &mut Option<Buffer>,
+    size: BufferAddress,
+    usage: BufferUsages,
+) -> Buffer {
+    if let Option::Some(buffer) = buffer
+        && buffer.size() >= size
+    {
+        return buffer.clone();
+    }
+
+    let new_buffer = device.create_buffer(&BufferDescriptor {
+        label: Option::None,
+        size: size.max(COPY_BUFFER_ALIGNMENT).next_power_of_two(),
+        usage,
+        mapped_at_creation: false,
+    });
+
+    info!("{new_buffer:?}");
+    buffer.insert(new_buffer).clone()
+}
+
+// replaces `texture` with a new one unless it already has the shape `descriptor` asks for
+fn reuse(
+    device: &Device,
+    texture: &mut Option<Texture>,
+    descriptor: &TextureDescriptor,
+) -> Texture {
+    if let Option::Some(texture) = texture
+        && texture.size() == descriptor.size
+        && texture.format() == descriptor.format
+        && texture.sample_count() == descriptor.sample_count
+    {
+        return texture.clone();
+    }
+
+    let new_texture = device.create_texture(descriptor);
+    info!("{new_texture:?}");
+    texture.insert(new_texture).clone()
+}
+
+fn write_attribute<T>(vertex: &mut [u8], attribute: &VertexAttribute, value: &T) {
+    vertex[attribute.offset as usize..][..attribute.format.size() as usize]
+        .copy_from_slice(unsafe { new_slice(value as *const _ as _, size_of::<T>()) });
+}
@@ -1,86 +1,834 @@
 use {
-    crate::args::Input as Kind,
-    eyre::Result,
-    serde::Deserialize,
-    serde_json::from_reader as json_from_reader,
-    std::io::{
-        stdin,
-        BufReader,
-        Read as _,
-    },
-    toml::from_str as toml_from_str,
+    crate::diagnostic::{
+        to_byte_offset,
+        to_offset,
+    },
+    clap::ValueEnum,
+    csscolorparser::{
+        parse as parse_css_color,
+        Color as CssColor,
+    },
+    eyre::{
+        bail,
+        ensure,
+        eyre,
+        Report,
+        Result,
+    },
+    ron::{
+        de::from_str as ron_from_str,
+        ser::{
+            to_string_pretty as ron_to_string,
+            PrettyConfig,
+        },
+    },
+    serde::{
+        Deserialize,
+        Serialize,
+    },
+    serde_json::{
+        from_str as json_from_str,
+        to_writer_pretty as json_to_writer,
+    },
+    serde_norway::{
+        from_str as yaml_from_str,
+        to_writer as yaml_to_writer,
+    },
+    std::{
+        ffi::OsStr,
+        io::{
+            Read,
+            Write,
+        },
+        ops::Range,
+        path::Path,
+    },
+    toml::{
+        from_str as toml_from_str,
+        to_string as toml_to_string,
+        Value as TomlValue,
+    },
     tracing::info,
 };
 
-#[derive(Debug, Deserialize)]
-pub struct Input {
+/// Everything to draw, which is what an input file describes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Scene {
     pub canvas: Canvas,
     #[serde(default)]
     pub curve: Vec<Curve>,
 }
 
-impl Input {
-    pub fn deserialize(kind: Kind) -> Result<Self> {
-        let mut stdin = stdin().lock();
+impl Scene {
+    /// Reads a scene written in `format` from `reader`.
+    ///
+    /// [`SceneFile`](crate::SceneFile) reads the same but also points failures at their lines.
+    pub fn from_reader(format: SceneFormat, mut reader: impl Read) -> Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Self::parse(format, &text).map_err(|(error, _)| error)
+    }
 
-        let input = match kind {
-            Kind::Json => json_from_reader(BufReader::new(stdin))?,
-            Kind::Toml => {
-                let mut string = String::new();
-                stdin.read_to_string(&mut string)?;
-                toml_from_str(&string)?
-            },
+    // a failure comes with its span in bytes of `text` if the parser tells where it is, and its
+    // message leaves out the location so as not to repeat it in diagnostics
+    pub(crate) fn parse(
+        format: SceneFormat,
+        text: &str,
+    ) -> Result<Self, (Report, Option<Range<usize>>)> {
+        let without_location = |message: String, line: usize, column: usize| {
+            let location = format!(" at line {line} column {column}");
+            eyre!("{}", message.strip_suffix(&location).unwrap_or(&message))
         };
 
-        info!("{input:?}");
-        Result::Ok(input)
+        let scene = match format {
+            SceneFormat::Json => json_from_str(text).map_err(|error| {
+                let span = (error.line() != 0).then(|| {
+                    // serde_json counts columns in bytes rather than characters
+                    let offset = to_byte_offset(text, error.line(), error.column());
+                    offset..offset
+                });
+
+                (
+                    without_location(error.to_string(), error.line(), error.column()),
+                    span,
+                )
+            }),
+            SceneFormat::Toml => {
+                toml_from_str(text).map_err(|error| (eyre!("{}", error.message()), error.span()))
+            },
+            SceneFormat::Yaml => yaml_from_str(text).map_err(|error| match error.location() {
+                Option::Some(location) => (
+                    without_location(error.to_string(), location.line(), location.column()),
+                    Option::Some(location.index()..location.index()),
+                ),
+                Option::None => (error.into(), Option::None),
+            }),
+            SceneFormat::Ron => ron_from_str(text).map_err(|error| {
+                let [start, end] = [error.span.start, error.span.end]
+                    .map(|position| to_offset(text, position.line, position.col));
+
+                (eyre!("{}", error.code), Option::Some(start..end))
+            }),
+        }?;
+
+        info!("{scene:?}");
+        Result::Ok(scene)
+    }
+
+    /// Writes the scene in `format` to `writer`, where every field is written even if it has a
+    /// default value.
+    pub fn to_writer(&self, format: SceneFormat, mut writer: impl Write) -> Result<()> {
+        match format {
+            SceneFormat::Json => json_to_writer(&mut writer, self)?,
+            SceneFormat::Toml => {
+                let mut value = TomlValue::try_from(self)?;
+                shorten_floats(&mut value);
+                writer.write_all(toml_to_string(&value)?.as_bytes())?;
+            },
+            SceneFormat::Yaml => yaml_to_writer(writer, self)?,
+            SceneFormat::Ron => {
+                writer.write_all(ron_to_string(self, PrettyConfig::default())?.as_bytes())?;
+            },
+        }
+
+        Result::Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SceneFormat {
+    Json,
+    Toml,
+    #[value(alias = "yml")]
+    Yaml,
+    Ron,
+}
+
+impl SceneFormat {
+    /// Tells the format of a scene file from its extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        from_extension(path)
     }
 }
 
-#[derive(Debug, Deserialize)]
+// the format of a scene or image file whose extension is one of the names of the format
+pub(crate) fn from_extension<F: ValueEnum>(path: &Path) -> Result<F> {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .and_then(|extension| F::from_str(extension, true).ok())
+        .ok_or_else(|| {
+            eyre!(
+                "cannot tell the format of {} from its extension",
+                path.display()
+            )
+        })
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Canvas {
     pub size: [u32; 2],
-    pub color: u32,
+    /// The background, which is transparent if omitted.
+    #[serde(default)]
+    pub color: Color,
+    #[serde(default)]
+    pub antialias: Antialias,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Antialias {
+    #[default]
+    None,
+    Msaa4,
+    Msaa8,
+    Coverage,
+}
+
+impl Antialias {
+    /// How far strokes are widened on each side to leave room for fading out their edges.
+    pub fn feather(self) -> f32 {
+        match self {
+            Self::Coverage => 0.5,
+            _ => 0.0,
+        }
+    }
+
+    pub fn sample_count(self) -> u32 {
+        match self {
+            Self::None | Self::Coverage => 1,
+            Self::Msaa4 => 4,
+            Self::Msaa8 => 8,
+        }
+    }
+}
+
+/// A color with straight alpha, whose components range from 0 to 1.
+///
+/// It is written as an integer `0xRRGGBB`, a CSS color such as `"#RRGGBBAA"`, `"red"` or
+/// `"hsl(120, 100%, 50%)"`, or an array `[r, g, b, a]`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "RawColor", into = "RawColor")]
+pub struct Color(pub [f32; 4]);
+
+impl Color {
+    pub fn rgb(self) -> [f32; 3] {
+        let [r, g, b, _] = self.0;
+        [r, g, b]
+    }
+
+    pub fn alpha(self) -> f32 {
+        self.0[3]
+    }
+
+    /// The color multiplied by its alpha, which is how it is blended.
+    pub fn premultiplied(self) -> [f32; 4] {
+        let [r, g, b, a] = self.0;
+        [r * a, g * a, b * a, a]
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl Default for Color {
+    fn default() -> Self {
+        Self([0.0; 4])
+    }
+}
+
+// how a color is written in a scene
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawColor {
+    Rgb(u32),
+    Css(String),
+    Rgba([f32; 4]),
+}
+
+impl TryFrom<RawColor> for Color {
+    type Error = Report;
+
+    fn try_from(raw: RawColor) -> Result<Self> {
+        let rgba = match raw {
+            RawColor::Rgb(rgb) => {
+                let [a, r, g, b] = rgb.to_be_bytes();
+                ensure!(a == 0, "{:X} is invalid as RGB", rgb);
+                CssColor::from_rgba8(r, g, b, u8::MAX).to_array()
+            },
+            RawColor::Css(css) => {
+                // the parser takes bare hex digits too, which are easily mistaken for names
+                ensure!(
+                    css.starts_with('#') || !css.chars().all(|c| c.is_ascii_hexdigit()),
+                    "{} is invalid as a color, whose hex digits need a leading #",
+                    css
+                );
+
+                parse_css_color(&css)
+                    .map_err(|error| eyre!("{} is invalid as a color: {}", css, error))?
+                    .to_array()
+            },
+            RawColor::Rgba(rgba) => {
+                ensure!(
+                    rgba.iter().all(|x| (0.0..=1.0).contains(x)),
+                    "{:?} is invalid as RGBA which ranges from 0 to 1",
+                    rgba
+                );
+
+                rgba
+            },
+        };
+
+        Result::Ok(Self(rgba))
+    }
+}
+
+// colors are written as hex strings unless they do not fit in 8 bits
+impl From<Color> for RawColor {
+    fn from(color: Color) -> Self {
+        let [r, g, b, a] = color.0;
+        let css = CssColor::new(r, g, b, a);
+        let rgba8 = css.to_rgba8();
+
+        match rgba8.map(|x| x as f32 / u8::MAX as f32) == color.0 {
+            true => {
+                Self::Css(CssColor::from_rgba8(rgba8[0], rgba8[1], rgba8[2], rgba8[3]).to_css_hex())
+            },
+            false => Self::Rgba(color.0),
+        }
+    }
+}
+
+/// A curve drawn as a stroke of a single color.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Curve {
     #[serde(flatten)]
     pub shape: Shape,
-    pub color: u32,
+    pub color: Color,
+    #[serde(flatten)]
+    pub stroke: Stroke,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Stroke {
+    #[serde(default = "Stroke::default_width")]
+    pub width: f32,
+    #[serde(default)]
+    pub join: Join,
+    #[serde(default = "Stroke::default_miter_limit")]
+    pub miter_limit: f32,
+    #[serde(default)]
+    pub cap: Cap,
+}
+
+impl Stroke {
+    fn default_miter_limit() -> f32 {
+        4.0
+    }
+
+    fn default_width() -> f32 {
+        1.0
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Join {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// The kind of a curve and its control points.
+///
+/// A closed curve ends where it starts. Lines and bezier curves go straight back to their first
+/// point, while splines wrap their control points around so that the seam is as smooth as the
+/// rest.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case", tag = "kind")]
 pub enum Shape {
     Lines {
         points: Vec<[f32; 2]>,
+        #[serde(default)]
+        closed: bool,
     },
     Bezier {
         points: Vec<[f32; 3]>,
-        samples: usize,
+        #[serde(default)]
+        closed: bool,
+        #[serde(flatten)]
+        sampling: Sampling,
         #[serde(flatten)]
         mode: BezierMode,
     },
     CatmullRom {
         points: Vec<[f32; 2]>,
-        samples: usize,
+        #[serde(default)]
+        closed: bool,
+        #[serde(default)]
+        endpoints: Endpoints,
+        /// How much the tangents are shortened, where zero keeps them and one flattens them out.
+        #[serde(default)]
+        tension: f32,
+        #[serde(flatten)]
+        sampling: Sampling,
         #[serde(flatten)]
         mode: CatmullRomMode,
     },
+    KochanekBartels {
+        points: Vec<[f32; 2]>,
+        #[serde(default)]
+        closed: bool,
+        #[serde(default)]
+        endpoints: Endpoints,
+        #[serde(default)]
+        tension: PerPoint,
+        /// How sharp the corners at the points are, where zero keeps the tangents on both sides
+        /// the same.
+        #[serde(default)]
+        continuity: PerPoint,
+        /// Which side the tangents lean to, where a negative bias follows the next point.
+        #[serde(default)]
+        bias: PerPoint,
+        #[serde(flatten)]
+        sampling: Sampling,
+    },
+    Hermite {
+        points: Vec<[f32; 2]>,
+        #[serde(default)]
+        closed: bool,
+        #[serde(default)]
+        tangents: Tangents,
+        /// How much cardinal tangents are shortened, which is only given to them.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tension: Option<f32>,
+        #[serde(flatten)]
+        sampling: Sampling,
+    },
+    BSpline {
+        degree: usize,
+        points: Vec<[f32; 2]>,
+        #[serde(default)]
+        closed: bool,
+        #[serde(default)]
+        knots: Knots,
+        #[serde(flatten)]
+        sampling: Sampling,
+    },
+    Nurbs {
+        degree: usize,
+        points: Vec<[f32; 3]>,
+        #[serde(default)]
+        closed: bool,
+        #[serde(default)]
+        knots: Knots,
+        #[serde(flatten)]
+        sampling: Sampling,
+    },
+}
+
+impl Shape {
+    /// The name of the kind as it is written in a scene.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Lines {
+                ..
+            } => "lines",
+            Self::Bezier {
+                ..
+            } => "bezier",
+            Self::CatmullRom {
+                ..
+            } => "catmull_rom",
+            Self::KochanekBartels {
+                ..
+            } => "kochanek_bartels",
+            Self::Hermite {
+                ..
+            } => "hermite",
+            Self::BSpline {
+                ..
+            } => "b_spline",
+            Self::Nurbs {
+                ..
+            } => "nurbs",
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        match self {
+            Self::Lines {
+                closed, ..
+            }
+            | Self::Bezier {
+                closed, ..
+            }
+            | Self::CatmullRom {
+                closed, ..
+            }
+            | Self::KochanekBartels {
+                closed, ..
+            }
+            | Self::Hermite {
+                closed, ..
+            }
+            | Self::BSpline {
+                closed, ..
+            }
+            | Self::Nurbs {
+                closed, ..
+            } => *closed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Sampling {
+    #[serde(flatten)]
+    pub density: Density,
+    #[serde(default, rename = "sampling")]
+    pub spacing: Spacing,
+    /// Tolerance in pixels of the polyline which approximates a curve to measure its length.
+    #[serde(default = "Sampling::default_accuracy")]
+    pub accuracy: f32,
+}
+
+impl Sampling {
+    fn default_accuracy() -> f32 {
+        0.01
+    }
+}
+
+/// How many points are taken from a curve, either as a fixed count or as a tolerance in pixels of
+/// the distance between the curve and the line strip.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(try_from = "RawDensity", into = "RawDensity")]
+pub enum Density {
+    Samples { samples: usize },
+    Tolerance { tolerance: f32 },
+}
+
+// how a density is written in a scene, where exactly one of the fields is given
+#[derive(Deserialize, Serialize)]
+struct RawDensity {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    samples: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tolerance: Option<f32>,
+}
+
+impl TryFrom<RawDensity> for Density {
+    type Error = Report;
+
+    fn try_from(raw: RawDensity) -> Result<Self> {
+        match (raw.samples, raw.tolerance) {
+            (Option::Some(samples), Option::None) => Result::Ok(Self::Samples {
+                samples,
+            }),
+            (Option::None, Option::Some(tolerance)) => Result::Ok(Self::Tolerance {
+                tolerance,
+            }),
+            (Option::None, Option::None) => {
+                bail!("need either samples or tolerance to sample a curve")
+            },
+            (Option::Some(_), Option::Some(_)) => {
+                bail!("need only one of samples and tolerance to sample a curve")
+            },
+        }
+    }
+}
+
+impl From<Density> for RawDensity {
+    fn from(density: Density) -> Self {
+        match density {
+            Density::Samples {
+                samples,
+            } => Self {
+                samples: Option::Some(samples),
+                tolerance: Option::None,
+            },
+            Density::Tolerance {
+                tolerance,
+            } => Self {
+                samples: Option::None,
+                tolerance: Option::Some(tolerance),
+            },
+        }
+    }
+}
+
+/// Where points are taken from a curve, either at even intervals of its parameter or at even
+/// intervals of its length.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spacing {
+    #[default]
+    Parameter,
+    ArcLength,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case", tag = "mode")]
 pub enum BezierMode {
     Normal,
     DeCasteljau,
 }
 
-#[derive(Debug, Deserialize)]
+/// How far apart the knots of a catmull rom spline are, which is the distance between the points
+/// raised to the power of alpha. The others are presets of `Alpha`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case", tag = "mode")]
 pub enum CatmullRomMode {
     Uniform,
     Chordal,
     Centripetal,
+    Alpha { alpha: f32 },
+}
+
+impl CatmullRomMode {
+    pub fn alpha(&self) -> f32 {
+        match self {
+            Self::Uniform => 0.0,
+            Self::Chordal => 1.0,
+            Self::Centripetal => 0.5,
+            Self::Alpha {
+                alpha,
+            } => *alpha,
+        }
+    }
+}
+
+/// How an open catmull rom spline reaches its first and last points, which is through a phantom
+/// point beyond each of them unless they are skipped.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endpoints {
+    /// The spline only runs from the second point to the second last one.
+    #[default]
+    Skip,
+    /// The second point mirrored through the first one.
+    Reflect,
+    /// The point a step beyond the first one on the parabola through the first three points.
+    Extrapolate,
+    /// The first point again.
+    Duplicate,
+}
+
+/// A parameter of each point of a spline, which is either the same for all of them or given for
+/// each of them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PerPoint {
+    All(f32),
+    Each(Vec<f32>),
+}
+
+impl PerPoint {
+    /// The value of the `i`th point, which is zero if it is not given.
+    pub fn get(&self, i: usize) -> f32 {
+        match self {
+            Self::All(value) => *value,
+            Self::Each(values) => values.get(i).copied().unwrap_or(0.0),
+        }
+    }
+}
+
+impl Default for PerPoint {
+    fn default() -> Self {
+        Self::All(0.0)
+    }
+}
+
+/// The tangents of a hermite spline at its points, which are derivatives in a parameter going up
+/// by one from a point to the next.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Tangents {
+    Explicit(Vec<[f32; 2]>),
+    Mode(TangentMode),
+}
+
+impl Default for Tangents {
+    fn default() -> Self {
+        Self::Mode(TangentMode::FiniteDifference)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TangentMode {
+    /// The average of the differences to the neighbors, or the only difference at an end.
+    FiniteDifference,
+    /// Finite differences shortened by the tension.
+    Cardinal,
+    /// The tangents of Fritsch and Carlson, which keep data whose x coordinates increase from
+    /// overshooting. The parameter is then the x coordinate itself.
+    Monotone,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Knots {
+    Explicit(Vec<f32>),
+    Mode(KnotMode),
+}
+
+impl Default for Knots {
+    fn default() -> Self {
+        Self::Mode(KnotMode::Open)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnotMode {
+    Uniform,
+    #[serde(alias = "clamped")]
+    Open,
+    ChordLength,
+}
+
+// toml widens every f32 into f64, which would write 0.01 as 0.009999999776482582
+fn shorten_floats(value: &mut TomlValue) {
+    match value {
+        TomlValue::Float(float) => {
+            *float = (*float as f32).to_string().parse().unwrap_or(*float);
+        },
+        TomlValue::Array(array) => array.iter_mut().for_each(shorten_floats),
+        TomlValue::Table(table) => table
+            .iter_mut()
+            .for_each(|(_, value)| shorten_floats(value)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            Color,
+            Density,
+            Sampling,
+        },
+        serde_json::{
+            from_str as json_from_str,
+            to_string as json_to_string,
+        },
+    };
+
+    fn parse(json: &str) -> Option<[f32; 4]> {
+        json_from_str::<Color>(json).ok().map(|color| color.0)
+    }
+
+    #[test]
+    fn colors_are_read_in_every_form() {
+        assert_eq!(parse("16711680"), Option::Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(
+            parse(r##""#FF000080""##),
+            Option::Some([1.0, 0.0, 0.0, 128.0 / 255.0])
+        );
+        assert_eq!(parse(r##""#f00""##), Option::Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(parse(r#""red""#), Option::Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(
+            parse(r#""rgba(255, 0, 0, 0.5)""#),
+            Option::Some([1.0, 0.0, 0.0, 0.5])
+        );
+        assert_eq!(
+            parse(r#""hsl(120, 100%, 50%)""#),
+            Option::Some([0.0, 1.0, 0.0, 1.0])
+        );
+        assert_eq!(
+            parse("[0.25, 0.5, 0.75, 1.0]"),
+            Option::Some([0.25, 0.5, 0.75, 1.0])
+        );
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        assert_eq!(parse("16777216"), Option::None);
+        assert_eq!(parse("[1.5, 0.0, 0.0, 1.0]"), Option::None);
+        assert_eq!(parse("[0.0, 0.0, 0.0, -0.5]"), Option::None);
+        assert_eq!(parse(r#""nope""#), Option::None);
+        assert_eq!(parse(r#""bad""#), Option::None);
+        assert_eq!(parse(r#""FF0000""#), Option::None);
+    }
+
+    #[test]
+    fn colors_are_written_as_hex_unless_they_need_more_bits() {
+        let write = |rgba| json_to_string(&Color(rgba)).unwrap();
+
+        assert_eq!(write([1.0, 0.0, 0.0, 1.0]), r##""#ff0000""##);
+        assert_eq!(write([1.0, 0.0, 0.0, 0.0]), r##""#ff000000""##);
+        assert_eq!(write([0.25, 0.5, 0.75, 1.0]), "[0.25,0.5,0.75,1.0]");
+
+        for rgba in [
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 0.2, 0.4, 0.6],
+            [0.25, 0.5, 0.75, 1.0],
+        ] {
+            assert_eq!(parse(&write(rgba)), Option::Some(rgba));
+        }
+    }
+
+    fn density(json: &str) -> Result<Density, String> {
+        json_from_str::<Sampling>(json)
+            .map(|sampling| sampling.density)
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn densities_are_read_from_either_field() {
+        assert!(matches!(
+            density(r#"{"samples": 8}"#),
+            Result::Ok(Density::Samples {
+                samples: 8
+            })
+        ));
+        assert!(matches!(
+            density(r#"{"tolerance": 0.5, "sampling": "parameter"}"#),
+            Result::Ok(Density::Tolerance {
+                tolerance: 0.5
+            })
+        ));
+    }
+
+    #[test]
+    fn densities_need_exactly_one_field() {
+        let missing = density(r#"{"sampling": "parameter"}"#).unwrap_err();
+        assert!(
+            missing.contains("need either samples or tolerance"),
+            "{}",
+            missing
+        );
+
+        let both = density(r#"{"samples": 8, "tolerance": 0.5}"#).unwrap_err();
+        assert!(
+            both.contains("need only one of samples and tolerance"),
+            "{}",
+            both
+        );
+    }
+
+    #[test]
+    fn invalid_densities_are_reported_as_they_are() {
+        let negative = density(r#"{"samples": -1}"#).unwrap_err();
+        assert!(negative.contains("expected usize"), "{}", negative);
+
+        let string = density(r#"{"tolerance": "fine"}"#).unwrap_err();
+        assert!(string.contains("expected f32"), "{}", string);
+    }
+
+    #[test]
+    fn densities_are_written_as_the_field_they_were_read_from() {
+        for json in [r#"{"samples":8}"#, r#"{"tolerance":0.5}"#] {
+            let density = json_from_str::<Density>(json).unwrap();
+            assert_eq!(json_to_string(&density).unwrap(), json);
+        }
+    }
 }
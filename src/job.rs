@@ -0,0 +1,84 @@
+use {
+    crate::status::StatusCode,
+    eyre::{
+        Context as _,
+        Report,
+        Result,
+    },
+    serde_json::to_vec as to_json_vec,
+    std::{
+        fs::{
+            write as write_file,
+            File,
+        },
+        io::{
+            stdin,
+            stdout,
+            Write as _,
+        },
+        path::Path,
+    },
+    tracing::info,
+    utokyo_fsc_is4029l1_assignment_m1::{
+        Format,
+        Renderer,
+        SceneFile,
+        SceneFormat,
+    },
+};
+
+/// Where a scene is read from and where its image is written to, where no path means stdin or
+/// stdout.
+#[derive(Debug)]
+pub struct Job<'a> {
+    pub input: Option<&'a Path>,
+    pub input_format: SceneFormat,
+    pub output: Option<&'a Path>,
+    pub output_format: Format,
+    /// Where to write which renderer and adapter drew the image as JSON.
+    pub summary: Option<&'a Path>,
+}
+
+impl Job<'_> {
+    /// Reads, draws and writes the scene, failing with the status of the step which went wrong.
+    pub fn run(&self, renderer: &mut Renderer) -> Result<(), (StatusCode, Report)> {
+        let file = match self.input {
+            Option::Some(path) => File::open(path)
+                .map_err(Into::into)
+                .and_then(|file| {
+                    SceneFile::read(path.display().to_string(), self.input_format, file)
+                })
+                .wrap_err_with(|| format!("cannot read a scene from {}", path.display())),
+            Option::None => SceneFile::read("<stdin>", self.input_format, stdin().lock()),
+        }
+        .map_err(|error| (StatusCode::Input, error))?;
+
+        let scene = file.scene().map_err(|error| (StatusCode::Input, error))?;
+        let drawing = file
+            .drawing(&scene)
+            .map_err(|error| (StatusCode::Curve, error))?;
+
+        let (image, summary) = renderer
+            .render(&drawing, self.output_format)
+            .map_err(|error| (StatusCode::Output, error))?;
+
+        match self.output {
+            Option::Some(path) => write_file(path, &image)
+                .wrap_err_with(|| format!("cannot write an image to {}", path.display())),
+            Option::None => stdout().lock().write_all(&image).map_err(Into::into),
+        }
+        .map_err(|error| (StatusCode::Io, error))?;
+
+        info!("{summary:?}");
+
+        if let Option::Some(path) = self.summary {
+            to_json_vec(&summary)
+                .map_err(Into::into)
+                .and_then(|json| write_file(path, json))
+                .wrap_err_with(|| format!("cannot write a summary to {}", path.display()))
+                .map_err(|error| (StatusCode::Io, error))?;
+        }
+
+        Result::Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+//! Draws curves described by a scene into raster or vector images.
+//!
+//! A [`Scene`] is usually read from a JSON or TOML file by [`Scene::from_reader`], and
+//! [`render`] turns it into the bytes of an image file. [`Drawing`] and [`Renderer`] split the
+//! same work into sampling curves and encoding them, where a renderer keeps its gpu context to
+//! draw many scenes. [`sample`] gives the points of a single curve, and [`SceneFile`] points errors
+//! at the lines of a scene file they come from.
+
+mod curve;
+mod diagnostic;
+mod gpu;
+mod input;
+mod output;
+mod path;
+mod raster;
+mod stroke;
+mod vector;
+mod window;
+
+pub use crate::{
+    diagnostic::SceneFile,
+    input::{
+        Antialias,
+        BezierMode,
+        Canvas,
+        Cap,
+        CatmullRomMode,
+        Color,
+        Curve,
+        Density,
+        Endpoints,
+        Join,
+        KnotMode,
+        Knots,
+        PerPoint,
+        Sampling,
+        Scene,
+        SceneFormat,
+        Shape,
+        Spacing,
+        Stroke,
+        TangentMode,
+        Tangents,
+    },
+    output::{
+        AdapterSummary,
+        Backend,
+        Drawing,
+        Format,
+        Renderer,
+        RendererKind,
+        Summary,
+    },
+    window::preview,
+};
+use {
+    crate::curve::to_line_strip,
+    eyre::Result,
+};
+
+/// Samples `shape` into the points of a line strip, which is what is actually stroked.
+pub fn sample(shape: &Shape) -> Result<Vec<[f32; 2]>> {
+    to_line_strip(shape)
+}
+
+/// Renders `scene` into the bytes of an image file of `format`.
+///
+/// Raster formats are drawn on a gpu if there is one and on the cpu otherwise.
+pub fn render(scene: &Scene, format: Format) -> Result<Vec<u8>> {
+    let mut renderer = Renderer::new(RendererKind::Auto, Backend::Auto);
+    let (image, _) = renderer.render(&Drawing::new(scene)?, format)?;
+    Result::Ok(image)
+}
@@ -1,27 +1,29 @@
 mod args;
-mod curve;
-mod input;
-mod output;
+mod batch;
+mod job;
+mod preview;
 mod status;
+mod watch;
 
 use {
     crate::{
-        args::Arguments,
-        curve::to_line_strip,
-        input::Input,
-        output::{
-            generate_image,
-            LineStrip,
+        args::{
+            Arguments,
+            Command,
         },
+        job::Job,
         status::StatusCode,
     },
     color_eyre::config::HookBuilder,
     eyre::{
+        bail,
+        eyre,
         Context as _,
         Result,
     },
     std::{
         io::stderr,
+        path::Path,
         time::Instant,
     },
     tracing::{
@@ -32,6 +34,11 @@ use {
         fmt::Subscriber,
         util::SubscriberInitExt as _,
     },
+    utokyo_fsc_is4029l1_assignment_m1::{
+        Format,
+        Renderer,
+        SceneFormat,
+    },
 };
 
 fn main() -> StatusCode {
@@ -70,37 +77,67 @@ fn main() -> StatusCode {
         },
     };
 
-    let input = match Input::deserialize(args.input) {
-        Result::Ok(input) => input,
-        Result::Err(error) => {
-            error!("{error:?}");
-            return StatusCode::Input;
-        },
+    let status = match args.command {
+        Option::Some(Command::Batch(args)) => batch::run(args),
+        Option::Some(Command::Preview(args)) => preview::run(args),
+        Option::None => render_once(args),
     };
 
-    let line_strips = match input
-        .curve
-        .into_iter()
-        .map(|curve| {
-            Result::Ok(LineStrip {
-                positions: to_line_strip(curve.shape)?,
-                color: curve.color,
-            })
-        })
-        .collect::<Result<_>>()
-    {
-        Result::Ok(line_strips) => line_strips,
+    info!("{:?}", Instant::now().duration_since(start));
+    status
+}
+
+// reads a scene from stdin or a file and writes its image to stdout or a file
+fn render_once(args: Arguments) -> StatusCode {
+    // `-` stands for stdin or stdout just like leaving out a path
+    let [input, output] = [&args.input, &args.output]
+        .map(|path| path.as_deref().filter(|path| *path != Path::new("-")));
+
+    let formats = match (args.input_format, input) {
+        (Option::Some(format), _) => Result::Ok(format),
+        (Option::None, Option::Some(path)) => SceneFormat::from_path(path),
+        (Option::None, Option::None) => Result::Err(eyre!(
+            "need a format of the input to read a scene from stdin"
+        )),
+    }
+    .and_then(|input_format| {
+        let output_format = match (args.output_format, output) {
+            (Option::Some(format), _) => format,
+            (Option::None, Option::Some(path)) => Format::from_path(path)?,
+            (Option::None, Option::None) => {
+                bail!("need a format of the output to write an image to stdout")
+            },
+        };
+
+        Result::Ok((input_format, output_format))
+    });
+
+    let (input_format, output_format) = match formats {
+        Result::Ok(formats) => formats,
         Result::Err(error) => {
             error!("{error:?}");
-            return StatusCode::Curve;
+            return StatusCode::Clap;
         },
     };
 
-    if let Result::Err(error) = generate_image(args.output, input.canvas, line_strips) {
-        error!("{error:?}");
-        return StatusCode::Output;
-    }
+    let job = Job {
+        input,
+        input_format,
+        output,
+        output_format,
+        summary: args.summary.as_deref(),
+    };
 
-    info!("{:?}", Instant::now().duration_since(start));
-    StatusCode::Ok
+    let mut renderer = Renderer::new(args.render.renderer, args.render.backend);
+
+    match args.watch {
+        true => watch::run(&job, &mut renderer),
+        false => match job.run(&mut renderer) {
+            Result::Ok(()) => StatusCode::Ok,
+            Result::Err((status, error)) => {
+                error!("{error:?}");
+                status
+            },
+        },
+    }
 }
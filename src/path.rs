@@ -0,0 +1,44 @@
+/// An outline of a curve made of the commands which SVG, PDF and PostScript have in common.
+#[derive(Debug, Default)]
+pub struct Path(pub Vec<Command>);
+
+impl Path {
+    /// Connects `points` with straight lines, starting a new subpath after each non-finite point.
+    pub fn polyline(points: &[[f32; 2]]) -> Self {
+        let mut commands = Vec::with_capacity(points.len());
+        let mut is_drawing = false;
+
+        for point in points {
+            let is_finite = point.iter().all(|x| x.is_finite());
+
+            if is_finite {
+                commands.push(match is_drawing {
+                    true => Command::Line(*point),
+                    false => Command::Move(*point),
+                });
+            }
+
+            is_drawing = is_finite;
+        }
+
+        Self(commands)
+    }
+
+    /// Joins the end of the last subpath back to its start.
+    pub fn close(mut self) -> Self {
+        if !self.0.is_empty() {
+            self.0.push(Command::Close);
+        }
+
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    Move([f32; 2]),
+    Line([f32; 2]),
+    Quadratic([f32; 2], [f32; 2]),
+    Cubic([f32; 2], [f32; 2], [f32; 2]),
+    Close,
+}
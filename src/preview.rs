@@ -0,0 +1,71 @@
+use {
+    crate::{
+        args::PreviewArguments,
+        status::StatusCode,
+    },
+    eyre::{
+        Context as _,
+        Result,
+    },
+    std::{
+        fs::{
+            write as write_file,
+            File,
+        },
+        path::Path,
+    },
+    tracing::error,
+    utokyo_fsc_is4029l1_assignment_m1::{
+        preview,
+        Scene,
+        SceneFile,
+        SceneFormat,
+    },
+};
+
+/// Shows the scene file of `args` in a window until it is closed, saving the edited scene whenever
+/// asked to.
+pub fn run(args: PreviewArguments) -> StatusCode {
+    let output = args.output.as_deref().unwrap_or(&args.input);
+
+    let formats = SceneFormat::from_path(&args.input)
+        .and_then(|input_format| Result::Ok((input_format, SceneFormat::from_path(output)?)));
+
+    let (input_format, output_format) = match formats {
+        Result::Ok(formats) => formats,
+        Result::Err(error) => {
+            error!("{error:?}");
+            return StatusCode::Clap;
+        },
+    };
+
+    let scene = match File::open(&args.input)
+        .map_err(Into::into)
+        .and_then(|file| SceneFile::read(args.input.display().to_string(), input_format, file))
+        .wrap_err_with(|| format!("cannot read a scene from {}", args.input.display()))
+        .and_then(|file| file.scene())
+    {
+        Result::Ok(scene) => scene,
+        Result::Err(error) => {
+            error!("{error:?}");
+            return StatusCode::Input;
+        },
+    };
+
+    match preview(scene, args.backend, |scene| {
+        save(scene, output, output_format)
+    }) {
+        Result::Ok(()) => StatusCode::Ok,
+        Result::Err(error) => {
+            error!("{error:?}");
+            StatusCode::Output
+        },
+    }
+}
+
+// the whole scene is written into memory first so that a failure never leaves a broken file
+fn save(scene: &Scene, path: &Path, format: SceneFormat) -> Result<()> {
+    let mut data = Vec::new();
+    scene.to_writer(format, &mut data)?;
+    write_file(path, data).wrap_err_with(|| format!("cannot write a scene to {}", path.display()))
+}
@@ -0,0 +1,278 @@
+use {
+    crate::{
+        input::{
+            Antialias,
+            Canvas,
+        },
+        output::{
+            unpremultiply,
+            LineStrip,
+        },
+        stroke::{
+            coverage,
+            tessellate,
+            Vertex,
+        },
+    },
+    eyre::Result,
+    std::{
+        array::from_fn as new_array,
+        mem::{
+            replace,
+            take,
+        },
+    },
+    tracing::info,
+};
+
+/// Draws line strips into a RGBA8 buffer without a GPU.
+///
+/// This mirrors the pipeline in `gpu::Gpu::draw`: each line strip is tessellated into triangles
+/// which are filled following the top-left rule, the most covered of them in each sample is alpha
+/// blended over the background with premultiplied colors, and the rows are stored from top to
+/// bottom. Multisampling uses the standard sample positions of Vulkan, Metal and Direct3D, and
+/// shading happens once per pixel at its center.
+pub fn rasterize(canvas: &Canvas, line_strips: &[LineStrip]) -> Result<Vec<u8>> {
+    let [width, height] = canvas.size.map(|s| s as usize);
+
+    let mut target = Target::new(
+        width,
+        height,
+        sample_positions(canvas.antialias),
+        canvas.color.premultiplied().map(to_u8),
+    );
+
+    info!("rasterize {} line strips on the cpu", line_strips.len());
+
+    for line_strip in line_strips {
+        let vertices = tessellate(
+            &line_strip.positions,
+            &line_strip.stroke,
+            canvas.antialias.feather(),
+            line_strip.closed,
+        );
+
+        for triangle in vertices.chunks_exact(3) {
+            // canvas coordinates are y-up while rows of the buffer go downwards
+            let triangle = new_array(|i| Vertex {
+                position: [
+                    triangle[i].position[0],
+                    height as f32 - triangle[i].position[1],
+                ],
+                distance: triangle[i].distance,
+            });
+
+            target.fill_triangle(triangle, |distance| {
+                line_strip.color.alpha()
+                    * match canvas.antialias {
+                        Antialias::Coverage => coverage(distance, line_strip.stroke.width),
+                        _ => 1.0,
+                    }
+            });
+        }
+
+        target.composite(line_strip.color.rgb());
+    }
+
+    Result::Ok(target.resolve())
+}
+
+// the triangles of a line strip overlap at joins, caps and the insides of bends, so they only
+// raise the alpha of each sample in `alphas`, which are blended once the whole line strip is filled
+struct Target {
+    width: usize,
+    height: usize,
+    samples: &'static [[f32; 2]],
+    data: Vec<u8>,
+    alphas: Vec<f32>,
+    /// The samples whose alphas are not zero.
+    covered: Vec<usize>,
+}
+
+impl Target {
+    fn blend(&mut self, i: usize, color: [f32; 3], alpha: f32) {
+        let sample = &mut self.data[4 * i..][..4];
+        let dst = to_rgba(sample);
+        let src = [color[0] * alpha, color[1] * alpha, color[2] * alpha, alpha];
+        sample.copy_from_slice(&new_array::<_, 4, _>(|i| {
+            to_u8(src[i] + dst[i] * (1.0 - alpha))
+        }));
+    }
+
+    // blends every covered sample with its alpha and clears the alphas for the next line strip
+    fn composite(&mut self, color: [f32; 3]) {
+        let mut covered = take(&mut self.covered);
+
+        for i in covered.drain(..) {
+            let alpha = replace(&mut self.alphas[i], 0.0);
+            self.blend(i, color, alpha);
+        }
+
+        self.covered = covered;
+    }
+
+    fn cover(&mut self, i: usize, alpha: f32) {
+        if self.alphas[i] == 0.0 && alpha > 0.0 {
+            self.covered.push(i);
+        }
+
+        self.alphas[i] = self.alphas[i].max(alpha);
+    }
+
+    fn fill_triangle(&mut self, triangle: [Vertex; 3], alpha: impl Fn(f32) -> f32) {
+        let [a, b, c] = triangle;
+
+        if !a
+            .position
+            .iter()
+            .chain(&b.position)
+            .chain(&c.position)
+            .all(|x| x.is_finite())
+        {
+            return;
+        }
+
+        // wind every triangle the same way so that the inside is where all edges are positive
+        let (b, c) = match edge(a.position, b.position, c.position) < 0.0 {
+            true => (c, b),
+            false => (b, c),
+        };
+
+        let area = edge(a.position, b.position, c.position);
+
+        if area == 0.0 {
+            return;
+        }
+
+        let edges = [(a, b), (b, c), (c, a)].map(|(a, b)| (a.position, b.position));
+        let size = [self.width, self.height];
+
+        let [min, max] = [f32::min, f32::max].map(|f| {
+            new_array::<_, 2, _>(|i| {
+                (f(f(a.position[i], b.position[i]), c.position[i]) - 1.0)
+                    .ceil()
+                    .clamp(0.0, (size[i] - 1) as f32) as usize
+            })
+        });
+
+        for y in min[1]..=max[1] {
+            for x in min[0]..=max[0] {
+                // at most eight samples fit in a bit mask
+                let mask = self
+                    .samples
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, sample)| {
+                        let p = [x as f32 + sample[0], y as f32 + sample[1]];
+
+                        edges.iter().all(|(a, b)| {
+                            let e = edge(*a, *b, p);
+                            e > 0.0 || e == 0.0 && is_top_left(*a, *b)
+                        })
+                    })
+                    .fold(0_u8, |mask, (i, _)| mask | 1 << i);
+
+                if mask == 0 {
+                    continue;
+                }
+
+                // attributes are interpolated at the center even if only some samples are
+                // covered, just like the default interpolation on GPUs
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+
+                let alpha = alpha(
+                    (edge(b.position, c.position, p) * a.distance
+                        + edge(c.position, a.position, p) * b.distance
+                        + edge(a.position, b.position, p) * c.distance)
+                        / area,
+                );
+
+                let pixel = self.samples.len() * (y * self.width + x);
+
+                for i in 0..self.samples.len() {
+                    if mask & 1 << i != 0 {
+                        self.cover(pixel + i, alpha);
+                    }
+                }
+            }
+        }
+    }
+
+    fn new(width: usize, height: usize, samples: &'static [[f32; 2]], background: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            samples,
+            data: background.repeat(width * height * samples.len()),
+            alphas: vec![0.0; width * height * samples.len()],
+            covered: Vec::new(),
+        }
+    }
+
+    // averages the samples of every pixel like a resolve target does
+    fn resolve(self) -> Vec<u8> {
+        let count = self.samples.len();
+
+        let mut data = match count {
+            1 => self.data,
+            _ => self
+                .data
+                .chunks_exact(4 * count)
+                .flat_map(|samples| {
+                    let sum = samples
+                        .chunks_exact(4)
+                        .map(to_rgba)
+                        .fold([0.0; 4], |sum, x| new_array(|i| sum[i] + x[i]));
+
+                    sum.map(|x| to_u8(x / count as f32))
+                })
+                .collect(),
+        };
+
+        unpremultiply(&mut data);
+        data
+    }
+}
+
+// offsets from the top left corner of a pixel, in the y-down space of the buffer
+fn sample_positions(antialias: Antialias) -> &'static [[f32; 2]] {
+    match antialias {
+        Antialias::None | Antialias::Coverage => &[[0.5, 0.5]],
+        Antialias::Msaa4 => &[
+            [0.375, 0.125],
+            [0.875, 0.375],
+            [0.125, 0.625],
+            [0.625, 0.875],
+        ],
+        Antialias::Msaa8 => &[
+            [0.5625, 0.3125],
+            [0.4375, 0.6875],
+            [0.8125, 0.5625],
+            [0.3125, 0.1875],
+            [0.1875, 0.8125],
+            [0.0625, 0.4375],
+            [0.6875, 0.9375],
+            [0.9375, 0.0625],
+        ],
+    }
+}
+
+// twice the signed area of `a`, `b` and `p`, which is positive when `p` is on the right of `a` to
+// `b` in the y-down space of the buffer
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+// a top edge is horizontal with the inside below it, and a left edge has the inside on its right
+fn is_top_left(a: [f32; 2], b: [f32; 2]) -> bool {
+    a[1] == b[1] && b[0] > a[0] || b[1] < a[1]
+}
+
+fn to_rgba(pixel: &[u8]) -> [f32; 4] {
+    let u8_max = u8::MAX as f32;
+    [0, 1, 2, 3].map(|i| pixel[i] as f32 / u8_max)
+}
+
+fn to_u8(x: f32) -> u8 {
+    (x.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8
+}
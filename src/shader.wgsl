@@ -1,30 +1,50 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    // the distance from the centerline of a stroke and its width
+    @location(2) edge: vec2<f32>,
+    // the depth of a fully covered fragment of the curve and how much deeper an uncovered one is
+    @location(3) depth: vec2<f32>,
 }
 
 struct VertexOutput {
-    @builtin(position) position: vec4<f32>,
-    @location(0) color: vec3<f32>
+    // fragments are tested against depths written by the same triangles in another pipeline
+    @builtin(position) @invariant position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) edge: vec2<f32>,
+    @location(2) depth: vec2<f32>,
 }
 
 @vertex fn vertex_main(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
     output.position = vec4<f32>(input.position, 0.0, 1.0);
     output.color = input.color;
+    output.edge = input.edge;
+    output.depth = input.depth;
     return output;
 }
 
 struct FragmentInput {
-    @location(0) color: vec3<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) edge: vec2<f32>,
+    @location(2) depth: vec2<f32>,
 }
 
 struct FragmentOutput {
     @location(0) color: vec4<f32>,
+    // the more a fragment is covered the nearer it is, so that the depth of a sample ends up at the
+    // most covered fragment of a curve, which is the only one blended there
+    @builtin(frag_depth) depth: f32,
 }
 
 @fragment fn fragment_main(input: FragmentInput) -> FragmentOutput {
     var output: FragmentOutput;
-    output.color = vec4<f32>(input.color, 1.0);
+    // a box filter of one pixel over the stroke, which is the same as `stroke::coverage`
+    let width = input.edge.y;
+    let coverage = clamp(min(width / 2.0 + 0.5 - abs(input.edge.x), width), 0.0, 1.0);
+    // colors are premultiplied so that they are blended and resolved right over transparency
+    let alpha = input.color.a * coverage;
+    output.color = vec4<f32>(input.color.rgb * alpha, alpha);
+    output.depth = input.depth.x + (1.0 - coverage) * input.depth.y;
     return output;
 }
@@ -3,6 +3,7 @@ use std::process::{
     Termination,
 };
 
+#[derive(Debug, Clone, Copy)]
 pub enum StatusCode {
     Ok,
     Io,
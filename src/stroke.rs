@@ -0,0 +1,211 @@
+use {
+    crate::input::{
+        Cap,
+        Join,
+        Stroke,
+    },
+    nalgebra::Vector2,
+    std::f32::consts::PI,
+};
+
+/// Turns a line strip into a triangle list covering its stroke.
+///
+/// Every segment becomes a quad of the stroke width, the gap on the outer side of every inner
+/// point is filled with the join, and both ends get the cap. A `closed` line strip goes back from
+/// its last point to its first one and is joined there instead. Triangles may overlap each other
+/// and are not consistently wound.
+///
+/// The stroke is widened by `feather` on both sides so that its edges can be faded out by
+/// [`coverage`], which is what each [`Vertex::distance`] is for.
+pub fn tessellate(
+    positions: &[[f32; 2]],
+    stroke: &Stroke,
+    feather: f32,
+    closed: bool,
+) -> Vec<Vertex> {
+    let mut points = Vec::<Vector2<f32>>::with_capacity(positions.len());
+
+    for position in positions {
+        let point = Vector2::from(*position);
+
+        if points.last() != Option::Some(&point) {
+            points.push(point);
+        }
+    }
+
+    // the segment back to the start is made below
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let mut triangles = Triangles {
+        radius: stroke.width / 2.0 + feather,
+        feather,
+        vertices: Vec::new(),
+    };
+
+    match points[..] {
+        [] => (),
+        [point] => match stroke.cap {
+            Cap::Butt => (),
+            Cap::Round => triangles.fan(point, Vector2::x(), 2.0 * PI),
+            Cap::Square => {
+                let x = (triangles.radius - feather) * Vector2::x();
+                triangles.segment(point - x, point + x, Vector2::x());
+            },
+        },
+        _ if closed => {
+            let next = |i: usize| (i + 1) % points.len();
+
+            let directions = (0..points.len())
+                .map(|i| (points[next(i)] - points[i]).normalize())
+                .collect::<Vec<_>>();
+
+            for (i, d) in directions.iter().enumerate() {
+                triangles.segment(points[i], points[next(i)], *d);
+                triangles.join(points[next(i)], *d, directions[next(i)], stroke);
+            }
+        },
+        _ => {
+            let directions = points
+                .windows(2)
+                .map(|ps| (ps[1] - ps[0]).normalize())
+                .collect::<Vec<_>>();
+
+            for (ps, d) in points.windows(2).zip(&directions) {
+                triangles.segment(ps[0], ps[1], *d);
+            }
+
+            for (p, ds) in points[1..].iter().zip(directions.windows(2)) {
+                triangles.join(*p, ds[0], ds[1], stroke);
+            }
+
+            triangles.cap(points[0], -directions[0], stroke.cap);
+
+            triangles.cap(
+                points[points.len() - 1],
+                directions[directions.len() - 1],
+                stroke.cap,
+            );
+        },
+    }
+
+    triangles.vertices
+}
+
+/// How much of a pixel is covered by a stroke of `width` whose centerline is `distance` away.
+///
+/// This is the same box filter as the one in `shader.wgsl`.
+pub fn coverage(distance: f32, width: f32) -> f32 {
+    f32::min(width / 2.0 + 0.5 - distance.abs(), width).clamp(0.0, 1.0)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    /// Distance from the centerline of a stroke, which linearly varies across triangles.
+    pub distance: f32,
+}
+
+struct Triangles {
+    radius: f32,
+    feather: f32,
+    vertices: Vec<Vertex>,
+}
+
+impl Triangles {
+    // covers the end of a stroke which goes towards `d`
+    fn cap(&mut self, p: Vector2<f32>, d: Vector2<f32>, cap: Cap) {
+        match cap {
+            Cap::Butt => (),
+            Cap::Round => self.fan(p, normal(d), -PI),
+            Cap::Square => self.segment(p, p + (self.radius - self.feather) * d, d),
+        }
+    }
+
+    // sweeps `angle` counterclockwise from `from` around `center`
+    fn fan(&mut self, center: Vector2<f32>, from: Vector2<f32>, angle: f32) {
+        // keep the distance between an arc and its chords within a quarter of a pixel
+        let step = match self.radius > 0.25 {
+            true => 2.0 * (1.0 - 0.25 / self.radius).acos(),
+            false => PI / 2.0,
+        };
+
+        let count = (angle.abs() / step).ceil().max(1.0) as usize;
+        let from = self.radius * from.normalize();
+        let rotate = |i: usize| {
+            let (sin, cos) = (angle * i as f32 / count as f32).sin_cos();
+            center + Vector2::new(cos * from.x - sin * from.y, sin * from.x + cos * from.y)
+        };
+
+        for i in 0..count {
+            self.triangle(center, rotate(i), rotate(i + 1));
+        }
+    }
+
+    // fills the outer gap at `p` between a segment going towards `d0` and one going towards `d1`
+    fn join(&mut self, p: Vector2<f32>, d0: Vector2<f32>, d1: Vector2<f32>, stroke: &Stroke) {
+        let cross = d0.perp(&d1);
+
+        if cross.abs() <= f32::EPSILON && d0.dot(&d1) > 0.0 {
+            return;
+        }
+
+        // a left turn opens a gap on the right side
+        let side = match cross > 0.0 {
+            true => -1.0,
+            false => 1.0,
+        };
+
+        let [n0, n1] = [d0, d1].map(|d| side * normal(d));
+        let [a, b] = [n0, n1].map(|n| p + self.radius * n);
+
+        match stroke.join {
+            Join::Round => self.fan(p, n0, -side * n0.angle(&n1)),
+            Join::Bevel => self.triangle(p, a, b),
+            Join::Miter => {
+                let m = n0 + n1;
+                let cos = m.norm() / 2.0;
+
+                // the ratio of the miter length to the width is 1 / sin(θ / 2) where θ is the
+                // angle between the segments, which equals 1 / cos of the half turn
+                match cos > 0.0 && 1.0 / cos <= stroke.miter_limit {
+                    true => {
+                        let tip = p + self.radius / cos * m.normalize();
+                        self.triangle(p, a, tip);
+                        self.triangle(p, tip, b);
+                    },
+                    false => self.triangle(p, a, b),
+                }
+            },
+        }
+    }
+
+    // a quad from `p0` to `p1` whose centerline runs through the middle instead of a vertex
+    fn segment(&mut self, p0: Vector2<f32>, p1: Vector2<f32>, d: Vector2<f32>) {
+        let n = self.radius * normal(d);
+
+        let [a, b, c, e] = [(p0, 1.0), (p0, -1.0), (p1, -1.0), (p1, 1.0)].map(|(p, side)| Vertex {
+            position: (p + side * n).into(),
+            distance: side * self.radius,
+        });
+
+        self.vertices.extend([a, b, c, a, c, e]);
+    }
+
+    // `a` is on the centerline and the others are on the outline
+    fn triangle(&mut self, a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>) {
+        self.vertices
+            .extend(
+                [(a, 0.0), (b, self.radius), (c, self.radius)].map(|(p, distance)| Vertex {
+                    position: p.into(),
+                    distance,
+                }),
+            );
+    }
+}
+
+// the left hand side of `d`
+fn normal(d: Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-d.y, d.x)
+}
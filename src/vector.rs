@@ -0,0 +1,345 @@
+use {
+    crate::{
+        input::{
+            Canvas,
+            Cap,
+            Color,
+            Join,
+            Stroke,
+        },
+        output::LineStrip,
+        path::{
+            Command,
+            Path,
+        },
+    },
+    eyre::Result,
+    std::{
+        array::from_fn as new_array,
+        fmt::Write as _,
+    },
+    tracing::warn,
+};
+
+/// Writes curves as SVG paths on a page of the size of `canvas`.
+///
+/// The paths keep the y-up coordinates of the input and are flipped as a whole by a transform.
+pub fn write_svg(canvas: &Canvas, line_strips: &[LineStrip]) -> Result<Vec<u8>> {
+    let [width, height] = canvas.size;
+    let mut svg = String::new();
+    writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )?;
+
+    match canvas.color.alpha() {
+        0.0 => (),
+        1.0 => writeln!(
+            svg,
+            r#"<rect width="{width}" height="{height}" fill="{}"/>"#,
+            to_hex(canvas.color)
+        )?,
+        alpha => writeln!(
+            svg,
+            r#"<rect width="{width}" height="{height}" fill="{}" fill-opacity="{alpha}"/>"#,
+            to_hex(canvas.color)
+        )?,
+    }
+
+    writeln!(
+        svg,
+        r#"<g transform="matrix(1 0 0 -1 0 {height})" fill="none">"#
+    )?;
+
+    for line_strip in line_strips {
+        let mut d = String::new();
+
+        for command in &line_strip.path.0 {
+            if !d.is_empty() {
+                d.push(' ');
+            }
+
+            match command {
+                Command::Move([x, y]) => write!(d, "M {x} {y}")?,
+                Command::Line([x, y]) => write!(d, "L {x} {y}")?,
+                Command::Quadratic([x1, y1], [x, y]) => write!(d, "Q {x1} {y1} {x} {y}")?,
+                Command::Cubic([x1, y1], [x2, y2], [x, y]) => {
+                    write!(d, "C {x1} {y1} {x2} {y2} {x} {y}")?
+                },
+                Command::Close => write!(d, "Z")?,
+            }
+        }
+
+        // a lone point is a zero length subpath, which still gets round and square caps
+        if let [Command::Move([x, y])] = line_strip.path.0[..] {
+            write!(d, " L {x} {y}")?;
+        }
+
+        if d.is_empty() {
+            continue;
+        }
+
+        let stroke = &line_strip.stroke;
+
+        let opacity = match line_strip.color.alpha() {
+            1.0 => String::new(),
+            alpha => format!(r#" stroke-opacity="{alpha}""#),
+        };
+
+        writeln!(
+            svg,
+            r#"<path d="{d}" stroke="{}"{opacity} stroke-width="{}" stroke-linejoin="{}" stroke-miterlimit="{}" stroke-linecap="{}"/>"#,
+            to_hex(line_strip.color),
+            stroke.width,
+            match stroke.join {
+                Join::Miter => "miter",
+                Join::Round => "round",
+                Join::Bevel => "bevel",
+            },
+            stroke.miter_limit,
+            match stroke.cap {
+                Cap::Butt => "butt",
+                Cap::Round => "round",
+                Cap::Square => "square",
+            },
+        )?;
+    }
+
+    writeln!(svg, "</g>")?;
+    writeln!(svg, "</svg>")?;
+    Result::Ok(svg.into_bytes())
+}
+
+/// Writes curves as a single page PDF whose media box is the canvas.
+///
+/// PDF has the same y-up coordinate system as the input, so positions are written as they are.
+pub fn write_pdf(canvas: &Canvas, line_strips: &[LineStrip]) -> Result<Vec<u8>> {
+    let [width, height] = canvas.size;
+    let mut content = String::new();
+    let mut states = String::new();
+    let [r, g, b] = canvas.color.rgb();
+
+    // the fill alpha of the background has a graphics state of its own
+    match canvas.color.alpha() {
+        0.0 => (),
+        1.0 => writeln!(content, "{r} {g} {b} rg 0 0 {width} {height} re f")?,
+        alpha => {
+            write!(states, " /B << /ca {alpha} >>")?;
+            writeln!(content, "/B gs {r} {g} {b} rg 0 0 {width} {height} re f")?;
+        },
+    }
+
+    // stroke alphas are graphics states named after their indices in the resources of the page
+    let mut alphas = vec![1.0];
+    let mut current = 0;
+
+    for line_strip in line_strips {
+        if line_strip.path.0.is_empty() {
+            continue;
+        }
+
+        let alpha = line_strip.color.alpha();
+
+        let i = alphas.iter().position(|a| *a == alpha).unwrap_or_else(|| {
+            alphas.push(alpha);
+            alphas.len() - 1
+        });
+
+        if i != current {
+            writeln!(content, "/A{i} gs")?;
+            current = i;
+        }
+
+        let [r, g, b] = line_strip.color.rgb();
+        let [join, cap] = to_postscript_styles(&line_strip.stroke);
+
+        writeln!(
+            content,
+            "{r} {g} {b} RG {} w {join} j {cap} J {} M",
+            line_strip.stroke.width, line_strip.stroke.miter_limit
+        )?;
+
+        write_operators(&mut content, &line_strip.path, ["m", "l", "c", "h"])?;
+        writeln!(content, "S")?;
+    }
+
+    for (i, alpha) in alphas.iter().enumerate() {
+        write!(states, " /A{i} << /CA {alpha} >>")?;
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_owned(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width} {height}] /Resources << /ExtGState <<{states} >> >> /Contents 4 0 R >>"
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len()
+        ),
+    ];
+
+    // a comment of binary characters marks the file as binary for transfer programs
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+    }
+
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+
+    // every entry of a cross reference table is exactly 20 bytes long
+    for offset in offsets {
+        writeln!(trailer, "{offset:010} 00000 n ")?;
+    }
+
+    write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )?;
+
+    pdf.extend_from_slice(trailer.as_bytes());
+    Result::Ok(pdf)
+}
+
+/// Writes curves as an encapsulated PostScript whose bounding box is the canvas.
+///
+/// PostScript has the same y-up coordinate system as the input, so positions are written as they
+/// are.
+pub fn write_eps(canvas: &Canvas, line_strips: &[LineStrip]) -> Result<Vec<u8>> {
+    let [width, height] = canvas.size;
+    let mut eps = String::new();
+    writeln!(eps, "%!PS-Adobe-3.0 EPSF-3.0")?;
+    writeln!(eps, "%%BoundingBox: 0 0 {width} {height}")?;
+    writeln!(eps, "%%HiResBoundingBox: 0 0 {width} {height}")?;
+    writeln!(eps, "%%LanguageLevel: 2")?;
+    writeln!(eps, "%%EndComments")?;
+    writeln!(eps, "gsave")?;
+
+    // nothing is drawn where the canvas is transparent
+    if canvas.color.alpha() > 0.0 {
+        let [r, g, b] = canvas.color.rgb();
+        writeln!(eps, "{r} {g} {b} setrgbcolor 0 0 {width} {height} rectfill")?;
+    }
+
+    if ![0.0, 1.0].contains(&canvas.color.alpha())
+        || line_strips
+            .iter()
+            .any(|line_strip| line_strip.color.alpha() < 1.0)
+    {
+        warn!(
+            "EPS has no alpha, so translucent curves are mixed with an opaque canvas color and other colors are drawn opaque"
+        );
+    }
+
+    for line_strip in line_strips {
+        if line_strip.path.0.is_empty() {
+            continue;
+        }
+
+        // where curves overlap, they differ from those blended over each other
+        let [r, g, b] = match canvas.color.alpha() {
+            1.0 => {
+                let alpha = line_strip.color.alpha();
+                let background = canvas.color.rgb();
+                new_array(|i| line_strip.color.0[i] * alpha + background[i] * (1.0 - alpha))
+            },
+            _ => line_strip.color.rgb(),
+        };
+        let [join, cap] = to_postscript_styles(&line_strip.stroke);
+
+        writeln!(
+            eps,
+            "{r} {g} {b} setrgbcolor {} setlinewidth {join} setlinejoin {cap} setlinecap {} setmiterlimit",
+            line_strip.stroke.width, line_strip.stroke.miter_limit
+        )?;
+
+        writeln!(eps, "newpath")?;
+        write_operators(
+            &mut eps,
+            &line_strip.path,
+            ["moveto", "lineto", "curveto", "closepath"],
+        )?;
+        writeln!(eps, "stroke")?;
+    }
+
+    writeln!(eps, "grestore")?;
+    writeln!(eps, "showpage")?;
+    writeln!(eps, "%%EOF")?;
+    Result::Ok(eps.into_bytes())
+}
+
+// writes a path with the operators of PDF or PostScript, which have no quadratic curves
+fn write_operators(
+    out: &mut String,
+    path: &Path,
+    [r#move, line, curve, close]: [&str; 4],
+) -> Result<()> {
+    let mut current = [0.0; 2];
+    let mut start = [0.0; 2];
+
+    for command in &path.0 {
+        current = match *command {
+            Command::Move([x, y]) => {
+                writeln!(out, "{x} {y} {move}")?;
+                start = [x, y];
+                [x, y]
+            },
+            Command::Line([x, y]) => {
+                writeln!(out, "{x} {y} {line}")?;
+                [x, y]
+            },
+            Command::Quadratic(p1, p2) => {
+                // the control points of the same curve raised to a cubic one
+                let [x1, y1] = new_array(|i| current[i] + 2.0 / 3.0 * (p1[i] - current[i]));
+                let [x2, y2] = new_array(|i| p2[i] + 2.0 / 3.0 * (p1[i] - p2[i]));
+                let [x, y] = p2;
+                writeln!(out, "{x1} {y1} {x2} {y2} {x} {y} {curve}")?;
+                p2
+            },
+            Command::Cubic([x1, y1], [x2, y2], [x, y]) => {
+                writeln!(out, "{x1} {y1} {x2} {y2} {x} {y} {curve}")?;
+                [x, y]
+            },
+            Command::Close => {
+                writeln!(out, "{close}")?;
+                start
+            },
+        };
+    }
+
+    // a lone point is a zero length subpath, which still gets round and square caps
+    if let [Command::Move([x, y])] = path.0[..] {
+        writeln!(out, "{x} {y} {line}")?;
+    }
+
+    Result::Ok(())
+}
+
+// the operands of the line join and line cap operators, which PDF shares with PostScript
+fn to_postscript_styles(stroke: &Stroke) -> [u8; 2] {
+    [
+        match stroke.join {
+            Join::Miter => 0,
+            Join::Round => 1,
+            Join::Bevel => 2,
+        },
+        match stroke.cap {
+            Cap::Butt => 0,
+            Cap::Round => 1,
+            Cap::Square => 2,
+        },
+    ]
+}
+
+// only the color, as SVG takes its alpha apart
+fn to_hex(color: Color) -> String {
+    let [r, g, b] = color.rgb().map(|x| (x * u8::MAX as f32).round() as u8);
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
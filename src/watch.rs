@@ -0,0 +1,104 @@
+use {
+    crate::{
+        job::Job,
+        status::StatusCode,
+    },
+    eyre::{
+        Context as _,
+        OptionExt as _,
+        Result,
+    },
+    notify::{
+        recommended_watcher,
+        Event,
+        EventKind,
+        RecommendedWatcher,
+        RecursiveMode,
+        Watcher as _,
+    },
+    std::{
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::mpsc::{
+            channel,
+            Receiver,
+        },
+        time::Duration,
+    },
+    tracing::{
+        error,
+        info,
+        warn,
+    },
+    utokyo_fsc_is4029l1_assignment_m1::Renderer,
+};
+
+/// Runs `job` whenever its input changes, reporting failures instead of exiting on them.
+///
+/// `renderer` is kept between runs, so the gpu device is only requested once.
+pub fn run(job: &Job, renderer: &mut Renderer) -> StatusCode {
+    let input = match (job.input, job.output) {
+        (Option::Some(input), Option::Some(_)) => input,
+        _ => {
+            error!("need both --input and --output to watch a scene file");
+            return StatusCode::Clap;
+        },
+    };
+
+    let (input, _watcher, receiver) = match watch(input) {
+        Result::Ok(watch) => watch,
+        Result::Err(error) => {
+            error!("{error:?}");
+            return StatusCode::Io;
+        },
+    };
+
+    render(job, renderer);
+
+    // the channel is closed only when the watcher is gone
+    while let Result::Ok(result) = receiver.recv() {
+        match result {
+            Result::Ok(event) if is_change(&event, &input) => {
+                // editors may save a file in a few steps, so wait for them to settle down
+                while receiver.recv_timeout(Duration::from_millis(100)).is_ok() {}
+                render(job, renderer);
+            },
+            Result::Ok(_) => (),
+            Result::Err(error) => warn!("{error:?}"),
+        }
+    }
+
+    StatusCode::Ok
+}
+
+// the directory of the input is watched instead of the input itself because editors often replace
+// a file rather than write into it, which would end watching the file
+fn watch(input: &Path) -> Result<(PathBuf, RecommendedWatcher, Receiver<notify::Result<Event>>)> {
+    let input = input
+        .canonicalize()
+        .wrap_err_with(|| format!("cannot find {}", input.display()))?;
+
+    let directory = input
+        .parent()
+        .ok_or_eyre("cannot watch the directory of the input")?;
+
+    let (sender, receiver) = channel();
+    let mut watcher = recommended_watcher(sender)?;
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+    info!("watch {}", input.display());
+    Result::Ok((input, watcher, receiver))
+}
+
+fn is_change(event: &Event, input: &Path) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+        && event.paths.iter().any(|path| path == input)
+}
+
+fn render(job: &Job, renderer: &mut Renderer) {
+    match job.run(renderer) {
+        Result::Ok(()) => info!("render {:?}", job),
+        Result::Err((status, error)) => error!("{status:?}: {error:?}"),
+    }
+}
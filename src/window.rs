@@ -0,0 +1,441 @@
+use {
+    crate::{
+        gpu::Gpu,
+        input::{
+            BezierMode,
+            Cap,
+            CatmullRomMode,
+            Color,
+            Curve,
+            Join,
+            Scene,
+            Shape,
+            Stroke,
+        },
+        output::{
+            Backend,
+            Drawing,
+        },
+    },
+    eyre::{
+        Report,
+        Result,
+    },
+    std::sync::Arc,
+    tracing::{
+        error,
+        info,
+        warn,
+    },
+    wgpu::{
+        Surface,
+        SurfaceError,
+    },
+    winit::{
+        application::ApplicationHandler,
+        dpi::{
+            PhysicalPosition,
+            PhysicalSize,
+        },
+        event::{
+            ElementState,
+            KeyEvent,
+            MouseButton,
+            WindowEvent,
+        },
+        event_loop::{
+            ActiveEventLoop,
+            EventLoop,
+        },
+        keyboard::{
+            Key,
+            NamedKey,
+        },
+        window::{
+            Window,
+            WindowId,
+        },
+    },
+};
+
+/// How far from a control point in pixels of the canvas a click still grabs it.
+const GRAB_RADIUS: f32 = 8.0;
+
+/// Opens a window which shows `scene` along with its control points until it is closed.
+///
+/// Control points are moved by dragging them, and a click on one of them selects its curve. `M`
+/// switches the mode of the selected curve, `S` passes the edited scene to `save`, and `Escape`
+/// closes the window. Failures while editing are only reported so that the window stays open.
+pub fn preview(
+    scene: Scene,
+    backend: Backend,
+    save: impl FnMut(&Scene) -> Result<()>,
+) -> Result<()> {
+    let event_loop = EventLoop::new()?;
+
+    let mut preview = Preview {
+        scene,
+        backend,
+        save,
+        screen: Option::None,
+        cursor: [0.0; 2],
+        selected: Option::None,
+        dragged: Option::None,
+        error: Option::None,
+    };
+
+    event_loop.run_app(&mut preview)?;
+    preview.error.map_or(Result::Ok(()), Result::Err)
+}
+
+struct Preview<F> {
+    scene: Scene,
+    backend: Backend,
+    save: F,
+    screen: Option<Screen>,
+    /// Where the cursor is in the coordinates of the canvas.
+    cursor: [f32; 2],
+    /// The index of the curve whose control point was clicked last.
+    selected: Option<usize>,
+    /// The indices of the curve and its control point which is being dragged.
+    dragged: Option<(usize, usize)>,
+    /// What closed the window if it was not closed by the user.
+    error: Option<Report>,
+}
+
+struct Screen {
+    window: Arc<Window>,
+    surface: Surface<'static>,
+    gpu: Gpu,
+}
+
+impl<F: FnMut(&Scene) -> Result<()>> ApplicationHandler for Preview<F> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.screen.is_some() {
+            return;
+        }
+
+        match self.open(event_loop) {
+            Result::Ok(screen) => self.screen = Option::Some(screen),
+            Result::Err(error) => {
+                self.error = Option::Some(error);
+                event_loop.exit();
+            },
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let Option::Some(screen) = &self.screen else {
+            return;
+        };
+
+        match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::Escape),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => event_loop.exit(),
+            WindowEvent::Resized(size) => {
+                if let Result::Err(error) = screen.configure(size) {
+                    error!("{error:?}");
+                }
+            },
+            WindowEvent::RedrawRequested => {
+                if let Result::Err(error) = self.redraw() {
+                    error!("{error:?}");
+                }
+            },
+            WindowEvent::CursorMoved {
+                position, ..
+            } => {
+                self.cursor = screen.to_canvas(&self.scene, position);
+
+                if let Option::Some((i, j)) = self.dragged
+                    && let Option::Some(point) = control_points_mut(&mut self.scene.curve[i].shape)
+                        .into_iter()
+                        .nth(j)
+                {
+                    point.copy_from_slice(&self.cursor);
+                    screen.window.request_redraw();
+                }
+            },
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => match state {
+                ElementState::Pressed => {
+                    self.dragged = self.grab();
+
+                    if let Option::Some((i, _)) = self.dragged {
+                        self.selected = Option::Some(i);
+                        screen.window.request_redraw();
+                    }
+                },
+                ElementState::Released => self.dragged = Option::None,
+            },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Character(character),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => match character.as_str() {
+                "m" | "M" => {
+                    switch_mode(self.selected.map(|i| &mut self.scene.curve[i]));
+                    screen.window.request_redraw();
+                },
+                "s" | "S" => match (self.save)(&self.scene) {
+                    Result::Ok(()) => info!("save the scene"),
+                    Result::Err(error) => error!("{error:?}"),
+                },
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+}
+
+impl<F> Preview<F> {
+    fn open(&self, event_loop: &ActiveEventLoop) -> Result<Screen> {
+        let [width, height] = self.scene.canvas.size;
+
+        let window = Arc::new(
+            event_loop.create_window(
+                Window::default_attributes()
+                    .with_title("preview")
+                    .with_inner_size(PhysicalSize::new(width, height)),
+            )?,
+        );
+
+        info!("{window:?}");
+        let (gpu, surface) = Gpu::with_surface(self.backend, window.clone())?;
+
+        let screen = Screen {
+            window,
+            surface,
+            gpu,
+        };
+
+        screen.configure(screen.window.inner_size())?;
+        Result::Ok(screen)
+    }
+
+    // the control points are drawn over the scene as markers joined by lines, and those of the
+    // selected curve stand out in another color
+    fn redraw(&mut self) -> Result<()> {
+        let Option::Some(screen) = &mut self.screen else {
+            return Result::Ok(());
+        };
+
+        let mut scene = self.scene.clone();
+
+        for (i, curve) in self.scene.curve.iter().enumerate() {
+            let points = control_points(&curve.shape);
+
+            let color = match self.selected == Option::Some(i) {
+                true => Color([1.0, 0.375, 0.0, 1.0]),
+                false => Color([0.0, 0.625, 1.0, 1.0]),
+            };
+
+            scene.curve.push(to_overlay(
+                points.clone(),
+                curve.shape.is_closed(),
+                Color([0.5, 0.5, 0.5, 1.0]),
+            ));
+
+            scene.curve.extend(points.iter().map(|[x, y]| {
+                let r = GRAB_RADIUS / 2.0;
+                to_overlay(
+                    vec![
+                        [x - r, y - r],
+                        [x + r, y - r],
+                        [x + r, y + r],
+                        [x - r, y + r],
+                    ],
+                    true,
+                    color,
+                )
+            }));
+        }
+
+        let drawing = Drawing::new(&scene)?;
+
+        let frame = match screen.surface.get_current_texture() {
+            Result::Ok(frame) => frame,
+            // the surface no longer matches the window, so it is configured again and drawn later
+            Result::Err(SurfaceError::Outdated | SurfaceError::Lost) => {
+                screen.configure(screen.window.inner_size())?;
+                screen.window.request_redraw();
+                return Result::Ok(());
+            },
+            Result::Err(error) => return Result::Err(error.into()),
+        };
+
+        screen
+            .gpu
+            .render_to_texture(&frame.texture, &drawing.canvas, &drawing.line_strips)?;
+
+        screen.window.pre_present_notify();
+        frame.present();
+        Result::Ok(())
+    }
+
+    // the topmost control point under the cursor, where later curves are drawn over earlier ones
+    fn grab(&self) -> Option<(usize, usize)> {
+        let [x, y] = self.cursor;
+
+        self.scene
+            .curve
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, curve)| {
+                control_points(&curve.shape)
+                    .iter()
+                    .rposition(|point| (point[0] - x).hypot(point[1] - y) <= GRAB_RADIUS)
+                    .map(|j| (i, j))
+            })
+    }
+}
+
+impl Screen {
+    fn configure(&self, size: PhysicalSize<u32>) -> Result<()> {
+        // a minimized window has no size to draw onto
+        if size.width == 0 || size.height == 0 {
+            return Result::Ok(());
+        }
+
+        self.gpu
+            .configure(&self.surface, [size.width, size.height])?;
+        self.window.request_redraw();
+        Result::Ok(())
+    }
+
+    // the canvas is stretched over the window and its y axis points upward
+    fn to_canvas(&self, scene: &Scene, position: PhysicalPosition<f64>) -> [f32; 2] {
+        let size = self.window.inner_size();
+        let [width, height] = scene.canvas.size.map(|s| s as f32);
+
+        [
+            position.x as f32 / size.width.max(1) as f32 * width,
+            (1.0 - position.y as f32 / size.height.max(1) as f32) * height,
+        ]
+    }
+}
+
+fn switch_mode(curve: Option<&mut Curve>) {
+    let Option::Some(curve) = curve else {
+        warn!("need to click a control point to select a curve before switching its mode");
+        return;
+    };
+
+    match &mut curve.shape {
+        Shape::Bezier {
+            mode, ..
+        } => {
+            *mode = match mode {
+                BezierMode::Normal => BezierMode::DeCasteljau,
+                BezierMode::DeCasteljau => BezierMode::Normal,
+            };
+
+            info!("{mode:?}");
+        },
+        Shape::CatmullRom {
+            mode, ..
+        } => {
+            *mode = match mode {
+                CatmullRomMode::Uniform => CatmullRomMode::Chordal,
+                CatmullRomMode::Chordal => CatmullRomMode::Centripetal,
+                CatmullRomMode::Centripetal => CatmullRomMode::Uniform,
+                // the presets never come back to a given alpha, which would be lost
+                CatmullRomMode::Alpha {
+                    alpha,
+                } => {
+                    warn!("cannot switch the mode of a catmull rom spline whose alpha is {alpha}");
+                    return;
+                },
+            };
+
+            info!("{mode:?}");
+        },
+        _ => warn!("cannot switch the mode of a curve which has none"),
+    }
+}
+
+fn control_points(shape: &Shape) -> Vec<[f32; 2]> {
+    match shape {
+        Shape::Lines {
+            points, ..
+        }
+        | Shape::CatmullRom {
+            points, ..
+        }
+        | Shape::KochanekBartels {
+            points, ..
+        }
+        | Shape::Hermite {
+            points, ..
+        }
+        | Shape::BSpline {
+            points, ..
+        } => points.clone(),
+        Shape::Bezier {
+            points, ..
+        }
+        | Shape::Nurbs {
+            points, ..
+        } => points.iter().map(|[x, y, _]| [*x, *y]).collect(),
+    }
+}
+
+// the positions of control points leaving out their weights
+fn control_points_mut(shape: &mut Shape) -> Vec<&mut [f32]> {
+    match shape {
+        Shape::Lines {
+            points, ..
+        }
+        | Shape::CatmullRom {
+            points, ..
+        }
+        | Shape::KochanekBartels {
+            points, ..
+        }
+        | Shape::Hermite {
+            points, ..
+        }
+        | Shape::BSpline {
+            points, ..
+        } => points.iter_mut().map(|point| &mut point[..]).collect(),
+        Shape::Bezier {
+            points, ..
+        }
+        | Shape::Nurbs {
+            points, ..
+        } => points.iter_mut().map(|point| &mut point[..2]).collect(),
+    }
+}
+
+fn to_overlay(points: Vec<[f32; 2]>, closed: bool, color: Color) -> Curve {
+    Curve {
+        shape: Shape::Lines {
+            points,
+            closed,
+        },
+        color,
+        stroke: Stroke {
+            width: 1.0,
+            join: Join::Miter,
+            miter_limit: 4.0,
+            cap: Cap::Butt,
+        },
+    }
+}
@@ -0,0 +1,555 @@
+use {
+    eyre::{
+        bail,
+        ensure,
+        OptionExt as _,
+        Result,
+    },
+    image::load_from_memory,
+    utokyo_fsc_is4029l1_assignment_m1::{
+        sample,
+        Backend,
+        Drawing,
+        Format,
+        Renderer,
+        RendererKind,
+        Scene,
+        SceneFormat,
+        Shape,
+    },
+};
+
+// the shape of a scene which only has the curve written as toml
+fn shape(curve: &str) -> Result<Shape> {
+    let text = format!("[canvas]\nsize = [100, 100]\n\n[[curve]]\ncolor = 0\n{curve}");
+    let scene = Scene::from_reader(SceneFormat::Toml, text.as_bytes())?;
+
+    Result::Ok(
+        scene
+            .curve
+            .into_iter()
+            .next()
+            .ok_or_eyre("cannot find the curve")?
+            .shape,
+    )
+}
+
+// a bezier curve through three points which are the same in every test
+fn bezier(closed: bool, mode: &str) -> Result<Vec<[f32; 2]>> {
+    sample(&shape(&format!(
+        r#"
+kind = "bezier"
+points = [[10, 10, 1], [50, 90, 2], [90, 10, 1]]
+closed = {closed}
+samples = 5
+mode = "{mode}"
+"#
+    ))?)
+}
+
+#[test]
+fn bezier_modes_agree() -> Result<()> {
+    for closed in [false, true] {
+        let normal = bezier(closed, "normal")?;
+        let de_casteljau = bezier(closed, "de_casteljau")?;
+
+        ensure!(
+            normal.len() == de_casteljau.len(),
+            "modes sample different numbers of points"
+        );
+
+        for (a, b) in normal.iter().zip(&de_casteljau) {
+            ensure!(
+                (a[0] - b[0]).hypot(a[1] - b[1]) < 1e-3,
+                "{:?} and {:?} differ",
+                a,
+                b
+            );
+        }
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn closed_bezier_returns_to_its_start() -> Result<()> {
+    for mode in ["normal", "de_casteljau"] {
+        let line_strip = bezier(true, mode)?;
+
+        ensure!(
+            line_strip[0] == [10.0, 10.0] && line_strip.first() == line_strip.last(),
+            "a closed bezier curve in {} mode does not return to its start",
+            mode
+        );
+
+        ensure!(
+            line_strip.windows(2).all(|ps| ps[0] != ps[1]),
+            "a closed bezier curve in {} mode repeats a point",
+            mode
+        );
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn arc_length_sampling_spaces_points_evenly() -> Result<()> {
+    let curve = |sampling: &str| {
+        sample(&shape(&format!(
+            r#"
+kind = "bezier"
+points = [[10, 10, 1], [20, 190, 1], [190, 90, 1], [60, 20, 1]]
+mode = "normal"
+samples = 32
+sampling = "{sampling}"
+"#
+        ))?)
+    };
+
+    let steps = |line_strip: Vec<[f32; 2]>| {
+        line_strip
+            .windows(2)
+            .map(|ps| (ps[1][0] - ps[0][0]).hypot(ps[1][1] - ps[0][1]))
+            .collect::<Vec<_>>()
+    };
+
+    // the parameter of this curve is far from its length, so only the length spaces it evenly
+    let spread = |steps: &[f32]| {
+        let [min, max] = [f32::min, f32::max].map(|f| steps.iter().copied().reduce(f).unwrap());
+        (max - min) / max
+    };
+
+    let parameter = steps(curve("parameter")?);
+    let arc_length = steps(curve("arc_length")?);
+
+    ensure!(
+        spread(&parameter) > 0.5,
+        "a curve sampled by its parameter is already even"
+    );
+
+    ensure!(
+        arc_length.len() == parameter.len() && spread(&arc_length) < 0.02,
+        "steps of {:?} along a curve sampled by arc length are uneven",
+        arc_length
+    );
+
+    let error = shape(
+        r#"
+kind = "bezier"
+points = [[10, 10, 1], [50, 90, 1], [90, 10, 1]]
+mode = "normal"
+tolerance = 0.5
+sampling = "arc_length"
+"#,
+    )
+    .and_then(|shape| sample(&shape))
+    .err()
+    .ok_or_eyre("a curve is sampled by arc length with a tolerance")?
+    .to_string();
+
+    ensure!(
+        error.starts_with("need samples instead of a tolerance"),
+        "a curve sampled by arc length with a tolerance is reported as {:?}",
+        error
+    );
+
+    Result::Ok(())
+}
+
+#[test]
+fn closed_b_spline_needs_repeating_knots() -> Result<()> {
+    let b_spline = |knots: &str| {
+        shape(&format!(
+            r#"
+kind = "b_spline"
+degree = 2
+points = [[10, 10], [90, 10], [90, 90], [10, 90]]
+closed = true
+knots = {knots}
+samples = 8
+"#
+        ))
+    };
+
+    let line_strip = sample(&b_spline("[0, 1, 2, 4, 5, 6, 7, 9, 10]")?)?;
+    let [first, .., second_last, _] = line_strip[..] else {
+        bail!("a closed b spline has too few points");
+    };
+
+    ensure!(
+        (first[0] - second_last[0]).hypot(first[1] - second_last[1]) < 20.0,
+        "a closed b spline with repeating knots jumps back to its start"
+    );
+
+    ensure!(
+        sample(&b_spline("[0, 1, 2, 3, 4, 5, 6, 7, 9]")?).is_err(),
+        "a closed b spline accepts knots whose intervals do not repeat"
+    );
+
+    Result::Ok(())
+}
+
+#[test]
+fn closed_b_spline_knots_are_counted_from_the_given_points() -> Result<()> {
+    let error = sample(&shape(
+        r#"
+kind = "b_spline"
+degree = 3
+points = [[10, 10], [90, 10], [90, 90], [10, 90]]
+closed = true
+knots = [0, 1, 2, 3, 4, 5, 6, 7]
+samples = 8
+"#,
+    )?)
+    .err()
+    .ok_or_eyre("a closed b spline is drawn with too few knots")?
+    .to_string();
+
+    ensure!(
+        error == "need 11 knots for a closed b spline of degree 3 with 4 points but 8 are given",
+        "a closed b spline with too few knots is reported as {:?}",
+        error
+    );
+
+    Result::Ok(())
+}
+
+#[test]
+fn weights_keep_curves_finite() -> Result<()> {
+    let curve = |kind: &str, points: &str, density: &str| {
+        let options = match kind {
+            "bezier" => "mode = \"normal\"",
+            _ => "degree = 2",
+        };
+
+        sample(&shape(&format!(
+            "kind = \"{kind}\"\npoints = {points}\n{density}\n{options}\n"
+        ))?)
+    };
+
+    let line_strip = curve(
+        "bezier",
+        "[[10, 10, 1], [50, 90, -0.5], [90, 10, 1]]",
+        "tolerance = 0.5",
+    )?;
+
+    ensure!(
+        line_strip.iter().flatten().all(|x| x.is_finite()),
+        "a bezier curve with a negative weight between its ends is not finite"
+    );
+
+    for (kind, points, density) in [
+        (
+            "bezier",
+            "[[10, 10, 0], [50, 90, 1], [90, 10, 1]]",
+            "samples = 8",
+        ),
+        (
+            "bezier",
+            "[[10, 10, 1], [50, 90, inf], [90, 10, 1]]",
+            "samples = 8",
+        ),
+        (
+            "bezier",
+            "[[10, 10, 1], [50, 90, -1], [90, 10, 1]]",
+            "tolerance = 0.5",
+        ),
+        (
+            "nurbs",
+            "[[10, 10, 1], [50, 90, 0], [90, 10, 1]]",
+            "samples = 8",
+        ),
+        (
+            "nurbs",
+            "[[10, 10, 1], [50, 90, -1], [90, 10, 1]]",
+            "samples = 8",
+        ),
+        (
+            "nurbs",
+            "[[10, 10, 1], [50, 90, nan], [90, 10, 1]]",
+            "samples = 8",
+        ),
+    ] {
+        ensure!(
+            curve(kind, points, density).is_err(),
+            "a {} with the points {} is drawn",
+            kind,
+            points
+        );
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn monotone_hermite_splines_do_not_overshoot() -> Result<()> {
+    let line_strip = sample(&shape(
+        r#"
+kind = "hermite"
+points = [[0, 0], [10, 0], [20, 50], [30, 52], [40, 100], [50, 100], [60, 0]]
+tangents = "monotone"
+samples = 16
+"#,
+    )?)?;
+
+    for ps in line_strip.windows(2) {
+        ensure!(ps[0][0] < ps[1][0], "{:?} goes back in x", ps);
+    }
+
+    // every span between two points stays between their y coordinates
+    for span in line_strip.windows(17).step_by(16) {
+        let [first, .., last] = span else {
+            bail!("a span of a monotone hermite spline has too few points");
+        };
+
+        let [low, high] = match first[1] <= last[1] {
+            true => [first[1], last[1]],
+            false => [last[1], first[1]],
+        };
+
+        ensure!(
+            span.iter()
+                .all(|p| low - 1e-3 <= p[1] && p[1] <= high + 1e-3),
+            "{:?} overshoots between {} and {}",
+            span,
+            low,
+            high
+        );
+
+        ensure!(
+            span.windows(2)
+                .all(|ps| (ps[1][1] - ps[0][1]) * (last[1] - first[1]) >= -1e-3),
+            "{:?} is not monotone",
+            span
+        );
+    }
+
+    ensure!(
+        shape(
+            r#"
+kind = "hermite"
+points = [[0, 0], [10, 0], [20, 50]]
+tangents = "monotone"
+tension = 0.5
+samples = 16
+"#
+        )
+        .and_then(|shape| sample(&shape))
+        .is_err(),
+        "a monotone hermite spline takes a tension"
+    );
+
+    Result::Ok(())
+}
+
+#[test]
+fn explicit_hermite_tangents_are_the_derivatives_at_the_ends() -> Result<()> {
+    const SAMPLES: usize = 1000;
+
+    let line_strip = sample(&shape(&format!(
+        r#"
+kind = "hermite"
+points = [[0, 0], [100, 0]]
+tangents = [[0, 300], [200, -100]]
+samples = {SAMPLES}
+"#
+    ))?)?;
+
+    let [first, second, .., second_last, last] = line_strip[..] else {
+        bail!("a hermite spline has too few points");
+    };
+
+    for (ps, tangent) in [
+        ([first, second], [0.0, 300.0]),
+        ([second_last, last], [200.0, -100.0]),
+    ] {
+        let derivative = [0, 1].map(|k| (ps[1][k] - ps[0][k]) * SAMPLES as f32);
+
+        ensure!(
+            (derivative[0] - tangent[0]).hypot(derivative[1] - tangent[1]) < 2.0,
+            "the derivative {:?} at an end differs from the tangent {:?}",
+            derivative,
+            tangent
+        );
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn kochanek_bartels_splines_without_parameters_are_uniform_catmull_rom_splines() -> Result<()> {
+    const POINTS: &str = "[[10, 10], [90, 20], [60, 50], [80, 90], [20, 70], [30, 40]]";
+
+    for (closed, endpoints) in [
+        (false, "skip"),
+        (false, "reflect"),
+        (false, "extrapolate"),
+        (false, "duplicate"),
+        (true, "skip"),
+    ] {
+        for tension in [0.0, 0.5] {
+            let curve = |kind: &str, options: &str| {
+                sample(&shape(&format!(
+                    r#"
+kind = "{kind}"
+points = {POINTS}
+closed = {closed}
+endpoints = "{endpoints}"
+tension = {tension}
+samples = 8
+{options}
+"#
+                ))?)
+            };
+
+            let catmull_rom = curve("catmull_rom", r#"mode = "uniform""#)?;
+            let kochanek_bartels = curve(
+                "kochanek_bartels",
+                "continuity = 0\nbias = [0, 0, 0, 0, 0, 0]",
+            )?;
+
+            ensure!(
+                catmull_rom.len() == kochanek_bartels.len()
+                    && catmull_rom
+                        .iter()
+                        .zip(&kochanek_bartels)
+                        .all(|(a, b)| (a[0] - b[0]).hypot(a[1] - b[1]) < 1e-3),
+                "a kochanek bartels spline differs from a catmull rom spline with {} endpoints",
+                endpoints
+            );
+        }
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn kochanek_bartels_parameters_shape_the_tangents_around_a_point() -> Result<()> {
+    const SAMPLES: usize = 1000;
+
+    let line_strip = sample(&shape(&format!(
+        r#"
+kind = "kochanek_bartels"
+points = [[0, 0], [100, 0], [100, 100], [0, 100]]
+endpoints = "reflect"
+tension = 0.5
+continuity = 0.5
+bias = -0.5
+samples = {SAMPLES}
+"#
+    ))?)?;
+
+    // the tangents entering and leaving the second point, which is where the second span starts
+    let [before, at, after] = [SAMPLES - 1, SAMPLES, SAMPLES + 1].map(|i| line_strip[i]);
+
+    ensure!(
+        at == [100.0, 0.0],
+        "the second span starts at {:?} instead of the second point",
+        at
+    );
+
+    for (ps, tangent) in [([before, at], [18.75, 18.75]), ([at, after], [6.25, 56.25])] {
+        let derivative = [0, 1].map(|k| (ps[1][k] - ps[0][k]) * SAMPLES as f32);
+
+        ensure!(
+            (derivative[0] - tangent[0]).hypot(derivative[1] - tangent[1]) < 1.0,
+            "the derivative {:?} around a point differs from the tangent {:?}",
+            derivative,
+            tangent
+        );
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn strokes_need_finite_widths_and_miter_limits() -> Result<()> {
+    for stroke in [
+        "width = 0",
+        "width = inf",
+        "width = nan",
+        "miter_limit = 0.5",
+        "miter_limit = inf",
+    ] {
+        let text = format!(
+            r#"
+[canvas]
+size = [100, 100]
+
+[[curve]]
+kind = "lines"
+points = [[10, 10], [90, 90]]
+color = 0
+{stroke}
+"#
+        );
+
+        let scene = Scene::from_reader(SceneFormat::Toml, text.as_bytes())?;
+
+        ensure!(
+            Drawing::new(&scene).is_err(),
+            "a stroke with {} is drawn",
+            stroke
+        );
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn translucent_strokes_are_blended_once() -> Result<()> {
+    for renderer in [RendererKind::Cpu, RendererKind::Auto] {
+        let mut renderer = Renderer::new(renderer, Backend::Auto);
+
+        for antialias in ["none", "msaa4", "coverage"] {
+            // sharp bends and round joins and caps overlap the triangles of the stroke a lot
+            let text = format!(
+                r##"
+[canvas]
+size = [256, 256]
+color = "white"
+antialias = "{antialias}"
+
+[[curve]]
+kind = "catmull_rom"
+mode = "centripetal"
+points = [[20, 20], [200, 40], [60, 120], [220, 230], [30, 200]]
+endpoints = "reflect"
+samples = 32
+color = "#FF000080"
+width = 12
+join = "round"
+cap = "round"
+"##
+            );
+
+            let scene = Scene::from_reader(SceneFormat::Toml, text.as_bytes())?;
+            let (png, _) = renderer.render(&Drawing::new(&scene)?, Format::Png)?;
+            let image = load_from_memory(&png)?.into_rgba8();
+
+            // edges are lighter where they are partly covered, but no pixel is darker than the
+            // stroke blended once over white
+            let stroke = image
+                .pixels()
+                .filter(|pixel| pixel.0 == [255, 127, 127, 255])
+                .count();
+
+            ensure!(
+                stroke > 4000,
+                "a translucent stroke with {} antialiasing has only {} pixels of its color",
+                antialias,
+                stroke
+            );
+
+            ensure!(
+                image
+                    .pixels()
+                    .all(|pixel| pixel.0[1] >= 127 && pixel.0[1] == pixel.0[2]),
+                "a translucent stroke with {} antialiasing is blended more than once somewhere",
+                antialias
+            );
+        }
+    }
+
+    Result::Ok(())
+}
@@ -0,0 +1,151 @@
+use {
+    eyre::{
+        ensure,
+        Result,
+    },
+    image::{
+        load_from_memory,
+        open as open_image,
+    },
+    std::{
+        fs::{
+            read_dir,
+            File,
+        },
+        path::PathBuf,
+    },
+    utokyo_fsc_is4029l1_assignment_m1::{
+        render,
+        sample,
+        Backend,
+        Drawing,
+        Format,
+        Renderer,
+        RendererKind,
+        Scene,
+        SceneFormat,
+    },
+};
+
+fn examples() -> Result<Vec<(PathBuf, Scene)>> {
+    let mut examples = Vec::new();
+
+    for entry in read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/examples"))? {
+        let path = entry?.path();
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            let scene = Scene::from_reader(SceneFormat::Toml, File::open(&path)?)?;
+            examples.push((path, scene));
+        }
+    }
+
+    ensure!(!examples.is_empty(), "cannot find any examples");
+    Result::Ok(examples)
+}
+
+#[test]
+fn examples_match_their_images() -> Result<()> {
+    let mut renderer = Renderer::new(RendererKind::Cpu, Backend::Auto);
+
+    for (path, scene) in examples()? {
+        let (png, _) = renderer.render(&Drawing::new(&scene)?, Format::Png)?;
+        let actual = load_from_memory(&png)?.into_rgba8();
+        let expected = open_image(path.with_extension("webp"))?.into_rgba8();
+
+        ensure!(
+            actual.dimensions() == expected.dimensions(),
+            "{} is drawn in a different size",
+            path.display()
+        );
+
+        // gpus and the cpu rasterizer may disagree on pixels exactly on the edges of strokes
+        let count = actual
+            .pixels()
+            .zip(expected.pixels())
+            .filter(|(a, b)| a != b)
+            .count();
+
+        ensure!(
+            count <= actual.len() / 4 / 100,
+            "{} differs from its image in {} pixels",
+            path.display(),
+            count
+        );
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn examples_render_as_vectors() -> Result<()> {
+    for (path, scene) in examples()? {
+        for (format, header) in [
+            (Format::Svg, b"<?xml".as_slice()),
+            (Format::Pdf, b"%PDF-"),
+            (Format::Eps, b"%!PS-"),
+        ] {
+            ensure!(
+                render(&scene, format)?.starts_with(header),
+                "{} is not rendered as {:?}",
+                path.display(),
+                format
+            );
+        }
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn examples_sample_every_curve() -> Result<()> {
+    for (path, scene) in examples()? {
+        for (i, curve) in scene.curve.iter().enumerate() {
+            ensure!(
+                !sample(&curve.shape)?.is_empty(),
+                "curve {} of {} has no points",
+                i,
+                path.display()
+            );
+        }
+    }
+
+    Result::Ok(())
+}
+
+#[test]
+fn examples_survive_being_written() -> Result<()> {
+    for (path, scene) in examples()? {
+        for format in [
+            SceneFormat::Json,
+            SceneFormat::Toml,
+            SceneFormat::Yaml,
+            SceneFormat::Ron,
+        ] {
+            let mut data = Vec::new();
+            scene.to_writer(format, &mut data)?;
+            let written = Scene::from_reader(format, data.as_slice())?;
+
+            ensure!(
+                written.curve.len() == scene.curve.len(),
+                "{} loses curves when written as {:?}",
+                path.display(),
+                format
+            );
+
+            for (i, (curve, written_curve)) in scene.curve.iter().zip(&written.curve).enumerate() {
+                ensure!(
+                    sample(&curve.shape)? == sample(&written_curve.shape)?,
+                    "curve {} of {} changes when written as {:?}",
+                    i,
+                    path.display(),
+                    format
+                );
+            }
+        }
+    }
+
+    Result::Ok(())
+}